@@ -1,8 +1,12 @@
 use near_sdk::borsh::{BorshDeserialize, BorshSerialize};
-use near_sdk::{env, near_bindgen, AccountId, PanicOnDefault, Promise};
-use near_sdk::collections::LookupMap;
+use near_sdk::collections::{LookupMap, LookupSet, TreeMap, UnorderedMap, UnorderedSet, Vector};
 use near_sdk::json_types::U128;
+use near_sdk::serde_json::{json, Value};
 use near_sdk::NearToken;
+use near_sdk::{
+    env, ext_contract, near, near_bindgen, AccountId, Gas, PanicOnDefault, Promise, PromiseError,
+};
+use std::collections::{HashMap, HashSet};
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Proposal {
@@ -12,13 +16,852 @@ pub struct Proposal {
     votes_against: u128,
     deadline: u64,
     finalized: bool,
+    /// Whether votes are weighted by the square root of the voter's balance
+    /// instead of the raw balance. Fixed at creation.
+    quadratic: bool,
+    /// Whether the proposal was cancelled before voting ended.
+    cancelled: bool,
+    /// Choices of a multiple choice proposal (empty for binary proposals).
+    options: Vec<String>,
+    /// Weight voted for each option, indexed like `options`.
+    vote_counts: Vec<u128>,
+    /// Whether votes are weighted by staked instead of liquid balance.
+    /// Fixed at creation.
+    use_staked_weight: bool,
+    /// Result cached at finalization: quorum reached and more weight for than
+    /// against, or for a multiple choice proposal, any weight on the winning option.
+    passed: bool,
+    /// Winning option cached at finalization (multiple choice proposals only).
+    winning_option: Option<u32>,
+    /// Circulating supply when the proposal was created.
+    supply_snapshot: u128,
+    /// Turnout, in tokens voted, needed for the proposal to pass, fixed at
+    /// creation. Measured by `raw_turnout`, so quadratic weighting and the early
+    /// vote bonus do not change it.
+    quorum: u128,
+    /// Account that created the proposal.
+    proposer: AccountId,
+    /// Action executed when the proposal passes, if any.
+    action: Option<ProposalAction>,
+    /// Whether the admin vetoed the proposal's action.
+    vetoed: bool,
+    veto_reason: Option<String>,
+    /// Number of accounts that endorsed the proposal (non-binding).
+    endorsement_count: u64,
+    /// Extra weight, in basis points, for votes cast before `early_vote_ends`.
+    /// Fixed at creation.
+    early_vote_bonus_bps: u16,
+    /// End of the early voting window (ns), fixed at creation.
+    early_vote_ends: u64,
+    /// Accounts that cast a vote, not counting delegators voted for by a delegate.
+    voter_count: u64,
+    /// Balance behind all votes cast, before quadratic weighting and the early
+    /// vote bonus. This is the turnout compared against `quorum`.
+    raw_turnout: u128,
+}
+
+/// JSON view of a governance proposal.
+#[near(serializers = [json])]
+pub struct ProposalView {
+    pub id: u64,
+    pub description: String,
+    pub votes_for: U128,
+    pub votes_against: U128,
+    pub deadline: u64,
+    pub finalized: bool,
+    pub quadratic: bool,
+    pub cancelled: bool,
+    pub options: Vec<String>,
+    pub vote_counts: Vec<U128>,
+    pub use_staked_weight: bool,
+    pub passed: bool,
+    pub winning_option: Option<u32>,
+    pub supply_snapshot: U128,
+    pub quorum: U128,
+    pub proposer: AccountId,
+    pub action: Option<ProposalAction>,
+    pub vetoed: bool,
+    pub veto_reason: Option<String>,
+    pub endorsement_count: u64,
+    pub early_vote_bonus_bps: u16,
+    pub early_vote_ends: u64,
+    pub voter_count: u64,
+    /// Weight voted across all sides or options.
+    pub total_votes: U128,
+    /// Tokens voted, before quadratic weighting and the early vote bonus.
+    pub raw_turnout: U128,
+    /// `raw_turnout` in basis points of `supply_snapshot` (`None` if it is 0).
+    pub turnout_bps: Option<u64>,
+    pub status: ProposalStatus,
+}
+
+impl From<Proposal> for ProposalView {
+    fn from(proposal: Proposal) -> Self {
+        let total_votes =
+            proposal.votes_for + proposal.votes_against + proposal.vote_counts.iter().sum::<u128>();
+        let turnout_bps = (proposal.supply_snapshot > 0)
+            .then(|| mul_div(proposal.raw_turnout, 10_000, proposal.supply_snapshot) as u64);
+        let status = proposal_status(&proposal, env::block_timestamp());
+        Self {
+            id: proposal.id,
+            description: proposal.description,
+            votes_for: U128(proposal.votes_for),
+            votes_against: U128(proposal.votes_against),
+            deadline: proposal.deadline,
+            finalized: proposal.finalized,
+            quadratic: proposal.quadratic,
+            cancelled: proposal.cancelled,
+            options: proposal.options,
+            vote_counts: proposal.vote_counts.into_iter().map(U128).collect(),
+            use_staked_weight: proposal.use_staked_weight,
+            passed: proposal.passed,
+            winning_option: proposal.winning_option,
+            supply_snapshot: U128(proposal.supply_snapshot),
+            quorum: U128(proposal.quorum),
+            proposer: proposal.proposer,
+            action: proposal.action,
+            vetoed: proposal.vetoed,
+            veto_reason: proposal.veto_reason,
+            endorsement_count: proposal.endorsement_count,
+            early_vote_bonus_bps: proposal.early_vote_bonus_bps,
+            early_vote_ends: proposal.early_vote_ends,
+            voter_count: proposal.voter_count,
+            total_votes: U128(total_votes),
+            raw_turnout: U128(proposal.raw_turnout),
+            turnout_bps,
+            status,
+        }
+    }
+}
+
+/// Price reported by the oracle's `get_price` view: each yoctoNEAR deposited
+/// buys `multiplier / 10^decimals` tokens. `timestamp` is in nanoseconds.
+#[near(serializers = [json])]
+pub struct OraclePrice {
+    pub multiplier: U128,
+    pub decimals: u8,
+    pub timestamp: u64,
+}
+
+/// Interface of the price oracle used by `mint_with_oracle`.
+#[ext_contract(ext_price_oracle)]
+pub trait PriceOracle {
+    fn get_price(&self) -> OraclePrice;
+}
+
+/// The part of NEP-141 used to pay out accepted-token proceeds.
+#[ext_contract(ext_fungible_token)]
+pub trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+/// A vote cast on a proposal, recorded per (proposal, account).
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct VoteRecord {
+    support: bool,
+    /// Chosen option for multiple choice proposals.
+    option: Option<u32>,
+    /// Weight added to the tally. Zero for accounts counted through their delegate.
+    weight: u128,
+}
+
+/// JSON view of a recorded vote.
+#[near(serializers = [json])]
+pub struct VoteView {
+    pub support: bool,
+    pub option: Option<u32>,
+    /// Zero for accounts counted through their delegate.
+    pub weight: U128,
+}
+
+/// An NFT persisted by `nft_mint`.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Nft {
+    owner: AccountId,
+    /// Original minter, who receives royalties on sales.
+    creator: AccountId,
+    metadata: String,
+    /// Royalty owed to the creator, in basis points of the sale price.
+    royalty_bps: u16,
+}
+
+/// JSON view of a stored NFT.
+#[near(serializers = [json])]
+pub struct TokenView {
+    pub token_id: u64,
+    pub owner_id: AccountId,
+    pub creator_id: AccountId,
+    pub metadata: String,
+    pub royalty_bps: u16,
+    /// Whether the token is non-transferable (staking lockup receipts).
+    pub soulbound: bool,
+}
+
+/// Intent carried in the `msg` of a NEP-141 transfer received by `ft_on_transfer`,
+/// e.g. `{"action":"buy_nft","metadata":"..."}`.
+#[near(serializers = [json])]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum FtTransferIntent {
+    /// Buy an NFT at the sending token's price; the sender becomes its owner
+    /// and creator.
+    BuyNft {
+        metadata: String,
+        royalty_bps: Option<u16>,
+    },
+}
+
+/// A locked stake created by `stake_locked`, represented in the owner's wallet
+/// by the soulbound receipt NFT `receipt_id`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone)]
+pub struct StakeLock {
+    /// Part of the owner's stake that cannot be unstaked before `unlock_at`.
+    pub amount: U128,
+    /// Block timestamp (ns) from which the stake can be unstaked.
+    pub unlock_at: u64,
+    pub receipt_id: u64,
+}
+
+/// NEP-199 payout: how the proceeds of an NFT sale are split between accounts.
+#[near(serializers = [json])]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
+
+/// Roles the admin can grant to delegate specific powers.
+/// The admin implicitly holds every role.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Role {
+    /// May mint tokens through permissioned minting paths.
+    Minter,
+    /// May pause and unpause the contract.
+    Pauser,
+    /// May create, finalize, and cancel governance proposals.
+    Governor,
+}
+
+/// Lifecycle stage of a proposal, used by `get_proposals_by_status`.
+#[near(serializers = [json])]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ProposalStatus {
+    /// Open for voting.
+    Active,
+    /// Voting ended; not finalized yet.
+    Ended,
+    Passed,
+    Failed,
+    Cancelled,
+    /// The admin vetoed the proposal's action.
+    Vetoed,
+}
+
+/// How the quorum of new proposals is set. The required turnout is fixed on
+/// each proposal when it is created.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum QuorumMode {
+    /// No quorum: any turnout can pass.
+    None,
+    /// A fixed amount of voting weight.
+    Absolute { amount: U128 },
+    /// A percentage (1-100) of the circulating supply at proposal creation.
+    Percentage { pct: u8 },
+}
+
+/// A halving emission schedule for staking rewards, set with
+/// `set_emission_schedule`. Nothing is emitted before `start`; from then the
+/// rate is `initial_rate_bps`, halving every `halving_interval_seconds`.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub struct EmissionSchedule {
+    /// Block timestamp (ns) at which emission starts.
+    pub start: u64,
+    pub initial_rate_bps: u16,
+    pub halving_interval_seconds: u64,
+}
+
+/// An action a binary proposal carries out when it is finalized as passed,
+/// unless the admin vetoed it.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub enum ProposalAction {
+    SetRewardRate { bps: u16 },
+    SetBurnBps { bps: u16 },
+    SetTreasury { treasury: AccountId },
+    SetPaused { paused: bool },
+    SetEmissionSchedule { schedule: Option<EmissionSchedule> },
+    BurnTreasury { amount: U128 },
+    SetMinVoteDuration { seconds: u64 },
+    SetMetadataIcon { icon: Option<String> },
+    SetFinalizationGrace { seconds: u64 },
+}
+
+/// A sensitive admin action that must be queued and wait out the timelock delay.
+#[near(serializers = [borsh, json])]
+#[derive(Clone, Debug)]
+pub enum TimelockedAction {
+    AdminBurn { account: AccountId, amount: U128 },
+    SetPaused { paused: bool },
+    SetRewardRate { bps: u16 },
+    SetTreasury { treasury: AccountId },
+    SetTimelockDelay { seconds: u64 },
+    SetDailyWithdrawLimit { limit: U128 },
+    MergeAccounts { from: AccountId, to: AccountId },
+    ReleaseReserve { amount: U128 },
+    RecoverNear { amount: U128 },
+}
+
+/// A queued timelocked action and the earliest time (ns) it may execute.
+#[near(serializers = [borsh, json])]
+pub struct QueuedAction {
+    pub action: TimelockedAction,
+    pub execute_after: u64,
+}
+
+/// Every tunable parameter plus headline state, returned by `get_contract_config`.
+#[near(serializers = [json])]
+pub struct ContractConfig {
+    pub version: String,
+    pub admin: AccountId,
+    pub treasury: AccountId,
+    pub total_supply: U128,
+    pub next_proposal_id: u64,
+    pub paused: bool,
+    pub reward_rate_bps: u16,
+    pub emission_schedule: Option<EmissionSchedule>,
+    pub slash_bps: u16,
+    pub min_stake_duration_seconds: u64,
+    pub min_stake: U128,
+    pub max_stake_per_account: U128,
+    pub transfer_cooldown_seconds: u64,
+    pub withdraw_fee_bps: u16,
+    pub min_withdraw: U128,
+    pub voting_period_seconds: u64,
+    pub min_vote_duration_seconds: u64,
+    pub finalization_grace_seconds: u64,
+    pub timelock_delay_seconds: u64,
+    pub nft_max_metadata_len: u32,
+    pub min_mint_deposit: U128,
+    pub max_supply: U128,
+    pub vote_lock_enabled: bool,
+    pub burn_bps: u16,
+    pub fee_to_pool_bps: u16,
+    pub nft_mint_price: U128,
+    pub nft_max_supply: u64,
+    pub redeem_rate_bps: u16,
+    pub min_stake_to_vote: U128,
+    pub min_stake_to_propose: U128,
+    pub max_active_proposals: u64,
+    pub early_vote_bonus_bps: u16,
+    pub early_vote_window_bps: u16,
+    pub oracle: Option<AccountId>,
+    pub dust_threshold: U128,
+    pub max_referral_supply: U128,
+    pub require_referrer_holder: bool,
+    pub transfers_enabled: bool,
+    pub quorum_mode: QuorumMode,
+    pub nft_boost_bps: u16,
+    pub daily_withdraw_limit: U128,
+}
+
+/// What `mint` would credit for a deposit, returned by `calculate_mint_output`.
+#[near(serializers = [json])]
+pub struct MintPreview {
+    /// Tokens minted for the deposit itself.
+    pub base: U128,
+    pub referrer: Option<AccountId>,
+    /// Bonus minted to the referrer.
+    pub referral_bonus: U128,
+    /// Tokens credited to the minting account.
+    pub net_credited: U128,
+    /// All tokens minted, including the referral bonus.
+    pub total_minted: U128,
+    /// Part of the deposit refunded because of the supply cap.
+    pub refunded: U128,
+}
+
+/// Supply figures and the latest `event_seq`, returned by `get_state_checkpoint`.
+/// A client can start from a checkpoint and apply every later event: each event
+/// that changes an account's liquid balance names the account and carries its
+/// post-event balance (see `emit_ft_batch`), or lists it in a `balances` array
+/// parallel to `account_ids` (`stake_batch`).
+#[near(serializers = [json])]
+pub struct StateCheckpoint {
+    pub total_supply: U128,
+    pub total_burned: U128,
+    pub event_seq: u64,
+    pub holder_count: u64,
+}
+
+/// Headline figures for explorers, returned by `get_contract_stats`.
+#[near(serializers = [json])]
+pub struct ContractStats {
+    pub total_supply: U128,
+    pub circulating_supply: U128,
+    pub total_staked: U128,
+    pub total_burned: U128,
+    pub holder_count: u64,
+    pub staker_count: u64,
+    pub reward_pool: U128,
+    /// Liquid balance of the treasury.
+    pub treasury_balance: U128,
+    pub next_proposal_id: u64,
+    pub active_proposal_count: u64,
+    pub paused: bool,
+}
+
+/// Cumulative supply changes by source, returned by `get_inflation_rate`.
+/// Counters other than `total_referral_minted` and `total_burned` start at the
+/// upgrade that introduced them.
+#[near(serializers = [json])]
+pub struct InflationStats {
+    pub total_deposit_minted: U128,
+    pub total_referral_minted: U128,
+    pub total_reward_minted: U128,
+    /// Issued by the Minter role through `mint_to`.
+    pub total_minter_minted: U128,
+    pub total_burned: U128,
+    /// Staking rewards accruing per year at the current rate and total stake.
+    pub annual_emission: U128,
+    /// `annual_emission` in basis points of total supply. Deposit mints are
+    /// backed by NEAR, so they are not counted as inflation.
+    pub annual_inflation_bps: u64,
+}
+
+/// Aggregate staking figures returned by `get_staking_stats`.
+#[near(serializers = [json])]
+pub struct StakingStats {
+    pub total_staked: U128,
+    pub staker_count: u64,
+    /// Rewards accruing per year at the current rate and total stake.
+    pub annual_emission: U128,
+    /// Tokens set aside for rewards, paid out before any rewards are minted.
+    pub reward_pool: Option<U128>,
+    /// Seconds until the reward pool runs dry at the current emission, after
+    /// which rewards are minted again; `None` while nothing is being emitted.
+    pub runway_seconds: Option<u64>,
+}
+
+/// Aggregated per-account data for profile pages, returned by `get_account_summary`.
+#[near(serializers = [json])]
+pub struct AccountSummary {
+    pub balance: U128,
+    pub staked: U128,
+    pub pending_rewards: U128,
+    pub referrer: Option<AccountId>,
+    pub tip_total: U128,
+    pub referral_count: u64,
+}
+
+/// Where an account's tokens sit, returned by `get_effective_balance`.
+#[near(serializers = [json])]
+pub struct BalanceBreakdown {
+    pub liquid: U128,
+    pub staked: U128,
+    /// Part of `liquid` locked by open votes.
+    pub vote_locked: U128,
+    /// What transfers, withdrawals and stakes can move right now.
+    pub spendable: U128,
+}
+
+/// Panic messages for user-facing failures. Each starts with a stable code
+/// (`E<n>: `) so clients can match on the code instead of the wording.
+pub mod errors {
+    // General
+    pub const CONTRACT_PAUSED: &str = "E1: Contract is paused";
+    pub const AMOUNT_NOT_POSITIVE: &str = "E2: Amount must be positive";
+    pub const INSUFFICIENT_BALANCE: &str = "E3: Insufficient balance";
+    pub const INSUFFICIENT_BALANCE_TO_BURN: &str = "E4: Insufficient balance to burn";
+    pub const BALANCE_LOCKED: &str = "E5: Balance is locked by an open vote";
+    pub const TOO_MANY_ACCOUNTS: &str = "E6: Too many accounts";
+    pub const REQUIRES_ONE_YOCTO: &str = "E7: Requires attached deposit of exactly 1 yoctoNEAR";
+    pub const ACCOUNT_NOT_REGISTERED: &str = "E64: Account has no balance or stake";
+
+    // Minting
+    pub const DEPOSIT_TOO_LOW: &str = "E8: Deposit too low";
+    pub const BELOW_DUST_THRESHOLD: &str = "E9: Resulting balance below the dust threshold";
+    pub const ORACLE_NOT_CONFIGURED: &str = "E10: Oracle not configured";
+    pub const NO_PENDING_REFUND: &str = "E72: No pending refund";
+    pub const MAX_SUPPLY_EXCEEDED: &str = "E60: Mint would exceed the max supply";
+
+    // Transfers, withdrawals and redemptions
+    pub const CANNOT_TIP_SELF: &str = "E11: Cannot tip yourself";
+    pub const LENGTH_MISMATCH: &str = "E75: Receivers and amounts differ in length";
+    pub const TRANSFERS_DISABLED: &str = "E12: Transfers are disabled";
+    pub const TRANSFER_COOLDOWN: &str = "E13: Transfer cooldown active";
+    pub const WITHDRAWAL_BELOW_MINIMUM: &str = "E14: Withdrawal below minimum";
+    pub const DAILY_LIMIT_EXCEEDED: &str = "E15: Daily withdrawal limit exceeded";
+    pub const REDEMPTION_DISABLED: &str = "E16: Redemption is disabled";
+    pub const REDEMPTION_TOO_SMALL: &str = "E17: Amount too small to redeem";
+    pub const REDEMPTION_NOT_COVERED: &str = "E18: Contract cannot cover the redemption";
+    pub const REDEMPTION_BELOW_MINIMUM: &str = "E61: Redemption payout below min_near_out";
+
+    // Allowances and signatures
+    pub const CANNOT_APPROVE_SELF: &str = "E19: Cannot approve yourself";
+    pub const INSUFFICIENT_ALLOWANCE: &str = "E20: Insufficient allowance";
+    pub const PERMIT_EXPIRED: &str = "E21: Permit expired";
+    pub const INVALID_SIGNATURE: &str = "E22: Invalid signature";
+    pub const SIGNATURE_LENGTH: &str = "E23: Signature must be 64 bytes";
+    pub const PUBLIC_KEY_LENGTH: &str = "E24: Public key must be 32 bytes";
+    pub const NO_SIGNING_KEY: &str = "E25: No signing key registered";
+    pub const WRONG_SIGNING_KEY: &str = "E26: Public key is not the owner's signing key";
+
+    // Staking
+    pub const INSUFFICIENT_BALANCE_TO_STAKE: &str = "E27: Insufficient balance to stake";
+    pub const INSUFFICIENT_STAKED: &str = "E28: Insufficient staked balance";
+    pub const STAKE_BELOW_MINIMUM: &str = "E29: Stake amount below minimum";
+    pub const STAKE_ABOVE_MAXIMUM: &str = "E30: Stake would exceed maximum per account";
+    pub const NO_REWARDS: &str = "E31: No rewards to claim";
+    pub const EXCEEDS_CLAIMABLE: &str = "E32: Amount exceeds claimable rewards";
+    pub const STAKE_LOCKED: &str = "E58: Stake is locked";
+    pub const INVALID_LOCK_DURATION: &str = "E73: Lock must be between 1 second and 4 years";
+
+    // Referrals
+    pub const CANNOT_REFER_SELF: &str = "E33: Cannot refer yourself";
+    pub const REFERRAL_EXISTS: &str = "E34: Referral already registered";
+    pub const REFERRER_NOT_HOLDER: &str = "E63: Referrer holds no tokens";
+
+    // Governance
+    pub const PROPOSAL_NOT_FOUND: &str = "E35: Proposal not found";
+    pub const PROPOSAL_CANCELLED: &str = "E36: Proposal was cancelled";
+    pub const PROPOSAL_FINALIZED: &str = "E37: Proposal already finalized";
+    pub const VOTING_ENDED: &str = "E38: Voting period has ended";
+    pub const VOTING_NOT_ENDED: &str = "E39: Voting period not ended";
+    pub const FINALIZATION_GRACE: &str = "E68: Finalization grace period not ended";
+    pub const VETO_WINDOW_ACTIVE: &str = "E74: Veto window not ended";
+    pub const PROPOSAL_VETOED: &str = "E70: Proposal was vetoed";
+    pub const ALREADY_ENDORSED: &str = "E71: Already endorsed";
+    pub const ALREADY_VOTED: &str = "E40: Already voted";
+    pub const NO_VOTING_POWER: &str = "E41: No voting power";
+    pub const VOTING_POWER_DELEGATED: &str = "E42: Voting power is delegated";
+    pub const STAKE_BELOW_VOTE_MINIMUM: &str = "E43: Staked balance below the minimum to vote";
+    pub const STAKE_BELOW_PROPOSE_MINIMUM: &str =
+        "E44: Staked balance below the minimum to propose";
+    pub const STAKER_PROPOSALS_DISABLED: &str = "E45: Staker proposals are disabled";
+    pub const USE_VOTE_OPTION: &str = "E46: Use vote_option for multiple choice proposals";
+    pub const NOT_MULTIPLE_CHOICE: &str = "E47: Proposal is not multiple choice";
+    pub const TOO_MANY_ACTIVE_PROPOSALS: &str = "E62: Too many active proposals";
+    pub const OPTION_OUT_OF_RANGE: &str = "E48: Option index out of range";
+    pub const CANNOT_DELEGATE_SELF: &str = "E49: Cannot delegate to yourself";
+    pub const DELEGATION_CYCLE: &str = "E50: Delegation cycle detected";
+    pub const TOO_MANY_DELEGATORS: &str = "E51: Delegate has too many delegators";
+    pub const NO_DELEGATION: &str = "E52: No delegation found";
+    pub const DELEGATION_TOO_DEEP: &str = "E69: Delegation chain too long";
+
+    // NFTs
+    pub const TOKEN_NOT_FOUND: &str = "E53: Token not found";
+    pub const NOT_TOKEN_OWNER: &str = "E54: Only the owner can transfer this token";
+    pub const CANNOT_TRANSFER_SELF: &str = "E55: Cannot transfer to yourself";
+    pub const NFT_SUPPLY_EXHAUSTED: &str = "E56: NFT supply exhausted";
+    pub const METADATA_TOO_LONG: &str = "E57: Metadata too long";
+    pub const TOKEN_SOULBOUND: &str = "E59: Token is soulbound";
+    pub const FT_NOT_ACCEPTED: &str = "E65: Token contract not accepted";
+    pub const INVALID_TRANSFER_MSG: &str = "E66: Invalid transfer msg";
+    pub const FT_PAYMENT_TOO_LOW: &str = "E67: Transferred amount below the NFT price";
+    pub const NFT_STORAGE_NOT_COVERED: &str =
+        "E76: NFT storage credit too low, call deposit_nft_storage first";
+    pub const NOT_PROPOSER_OR_GOVERNOR: &str =
+        "E77: Only the proposer or a governor can cancel a proposal";
+}
+
+/// Longest referral chain `admin_set_referral` walks when checking for cycles.
+const MAX_REFERRAL_CHAIN_DEPTH: u32 = 100;
+
+/// Maximum number of accounts that may delegate to a single delegate.
+/// Bounds the gas spent summing delegated balances in `vote`.
+const MAX_DELEGATORS: usize = 100;
+
+/// Longest delegation chain `delegate` walks when checking for cycles.
+const MAX_DELEGATION_DEPTH: u32 = 100;
+
+/// Default annual staking reward rate, in basis points of the staked amount (5%).
+const DEFAULT_REWARD_RATE_BPS: u16 = 500;
+
+/// Upper bound on the annual staking reward rate (100%).
+const MAX_REWARD_RATE_BPS: u16 = 10_000;
+
+/// One year in nanoseconds, the unit of `env::block_timestamp`.
+const YEAR_NS: u128 = 365 * 24 * 60 * 60 * 1_000_000_000;
+
+/// Computes `a * b / c` (floored) without overflowing on the full product,
+/// provided `(a % c) * b` fits in a `u128`.
+fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+    (a / c) * b + (a % c) * b / c
+}
+
+/// Adds `staked` token units held for `elapsed_ns` to a stake-seconds total of
+/// whole token-seconds plus a remainder in token-unit-nanoseconds. Nothing is
+/// rounded away, so frequent settles and small stakes still accumulate.
+fn add_stake_seconds(total: (u128, u128), staked: u128, elapsed_ns: u64) -> (u128, u128) {
+    let (seconds, nanos) = (
+        elapsed_ns as u128 / 1_000_000_000,
+        elapsed_ns as u128 % 1_000_000_000,
+    );
+    let (tokens, units) = (staked / STAKE_SECONDS_UNIT, staked % STAKE_SECONDS_UNIT);
+    let whole = total.0
+        + tokens * seconds
+        + units * seconds / STAKE_SECONDS_UNIT
+        + tokens * nanos / 1_000_000_000;
+    let remainder = total.1
+        + units * seconds % STAKE_SECONDS_UNIT * 1_000_000_000
+        + tokens * nanos % 1_000_000_000 * STAKE_SECONDS_UNIT
+        + units * nanos;
+    (
+        whole + remainder / STAKE_SECONDS_REMAINDER_UNIT,
+        remainder % STAKE_SECONDS_REMAINDER_UNIT,
+    )
+}
+
+/// `bps` basis points of `amount`, rounded down or up. Every split of an amount
+/// (fees, burns, bonuses, penalties, royalties) goes through here, and the
+/// caller keeps the other side as `amount - part`, so the parts always add up
+/// to `amount`. Amounts kept or destroyed by the protocol (fees, burns) round
+/// up; amounts paid out or minted (payouts, bonuses, royalties) and penalties
+/// round down, so rounding never mints, pays or slashes more than configured.
+fn apply_bps(amount: u128, bps: u16, round_up: bool) -> u128 {
+    let part = mul_div(amount, bps as u128, 10_000);
+    if round_up && !((amount % 10_000) * bps as u128).is_multiple_of(10_000) {
+        part + 1
+    } else {
+        part
+    }
+}
+
+/// Referral bonus, in basis points of the minted amount (1%).
+const REFERRAL_BONUS_BPS: u16 = 100;
+
+/// Upper bound on the early-unstake penalty (50%).
+const MAX_SLASH_BPS: u16 = 5_000;
+
+/// Maximum number of entries returned by a single paginated view.
+const MAX_PAGE_LIMIT: u64 = 100;
+
+/// Upper bound on NFT creator royalties (50%).
+const MAX_ROYALTY_BPS: u16 = 5_000;
+
+/// Maximum number of accounts accepted by a single batch query view.
+const MAX_BATCH_QUERY: usize = 100;
+
+/// Default minimum `mint` deposit (0.01 NEAR), enough to cover storage fees.
+const DEFAULT_MIN_MINT_DEPOSIT: u128 = 10_000_000_000_000_000;
+
+/// Upper bound on the share of each transfer that is burned (10%).
+const MAX_BURN_BPS: u16 = 1_000;
+
+/// Upper bound on the redemption rate (1:1).
+const MAX_REDEEM_RATE_BPS: u16 = 10_000;
+
+/// Gas reserved for the `on_redeem_resolved` callback.
+const REDEEM_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+
+/// Gas reserved for the `on_withdraw_resolved` callback.
+const WITHDRAW_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+
+/// Gas reserved for the `on_mint_refund_resolved` callback.
+const MINT_REFUND_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+
+/// Gas for the oracle's `get_price` view and for the `on_oracle_price` callback.
+const ORACLE_VIEW_GAS: Gas = Gas::from_tgas(10);
+const ORACLE_CALLBACK_GAS: Gas = Gas::from_tgas(20);
+
+/// Gas for an accepted token's `ft_transfer` and for the
+/// `on_ft_proceeds_withdrawn` callback.
+const FT_TRANSFER_GAS: Gas = Gas::from_tgas(10);
+const FT_TRANSFER_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+
+/// Gas reserved for the `on_nft_storage_refund_resolved` callback.
+const NFT_STORAGE_REFUND_CALLBACK_GAS: Gas = Gas::from_tgas(10);
+
+/// Oracle prices older than this are rejected (5 minutes).
+const MAX_ORACLE_PRICE_AGE_NS: u64 = 5 * 60 * 1_000_000_000;
+
+/// Largest accepted oracle price precision.
+const MAX_ORACLE_DECIMALS: u8 = 24;
+
+/// Upper bound on the NFT holder reward boost (+50%).
+const MAX_NFT_BOOST_BPS: u16 = 5_000;
+
+/// Upper bound on the early voter bonus (+50%).
+const MAX_EARLY_VOTE_BONUS_BPS: u16 = 5_000;
+
+/// Treasury tokens paid to the caller of `sweep_expired_locks` per account swept.
+const SWEEP_REWARD: u128 = 1_000_000_000_000_000_000;
+
+/// Token units per whole token in `stake_seconds` (tokens mint 1:1 with yoctoNEAR).
+const STAKE_SECONDS_UNIT: u128 = 1_000_000_000_000_000_000_000_000;
+
+/// Token-unit-nanoseconds per whole token-second, the modulus of the
+/// `stake_seconds` remainder.
+const STAKE_SECONDS_REMAINDER_UNIT: u128 = STAKE_SECONDS_UNIT * 1_000_000_000;
+
+/// Longest lock accepted by `stake_locked` (4 years).
+const MAX_STAKE_LOCK_SECONDS: u64 = 4 * 365 * 24 * 60 * 60;
+
+/// Length of a withdrawal limit window.
+const DAY_NS: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+/// Event keys naming an account, and the key its post-event liquid balance is
+/// logged under.
+const POST_BALANCE_KEYS: [(&str, &str); 7] = [
+    ("owner_id", "balance"),
+    ("old_owner_id", "old_owner_balance"),
+    ("new_owner_id", "new_owner_balance"),
+    ("account_id", "balance"),
+    ("treasury_id", "treasury_balance"),
+    ("from_account_id", "from_balance"),
+    ("to_account_id", "to_balance"),
+];
+
+/// Upper bound on the withdrawal fee (10%).
+const MAX_WITHDRAW_FEE_BPS: u16 = 1_000;
+
+/// Default delay before a queued timelocked action may execute (48 hours).
+const DEFAULT_TIMELOCK_DELAY_SECONDS: u64 = 48 * 60 * 60;
+
+/// Shortest delay `TimelockedAction::SetTimelockDelay` may set (24 hours).
+const MIN_TIMELOCK_DELAY_SECONDS: u64 = 24 * 60 * 60;
+
+/// Maximum token icon length in bytes (8KB).
+const MAX_ICON_LEN: usize = 8_192;
+
+/// Default maximum NFT metadata length in bytes (2KB).
+const DEFAULT_NFT_MAX_METADATA_LEN: u32 = 2_048;
+
+/// Allowed proposal description length in bytes.
+const MIN_DESCRIPTION_LEN: usize = 10;
+const MAX_DESCRIPTION_LEN: usize = 2_000;
+
+/// Default proposal voting period (7 days).
+const DEFAULT_VOTING_PERIOD_SECONDS: u64 = 7 * 24 * 60 * 60;
+
+/// Default floor on the voting period of new proposals (24 hours).
+const DEFAULT_MIN_VOTE_DURATION_SECONDS: u64 = 24 * 60 * 60;
+
+/// Upper bound on the proposal voting period (90 days).
+const MAX_VOTING_PERIOD_SECONDS: u64 = 90 * 24 * 60 * 60;
+
+/// Default limit on proposals open at the same time.
+const DEFAULT_MAX_ACTIVE_PROPOSALS: u64 = 50;
+
+/// Maximum number of proposals accepted by a single `finalize_expired` call.
+const MAX_FINALIZE_BATCH: usize = 50;
+
+/// How long after the voting deadline the admin may still veto a proposal (2 days).
+/// Proposals carrying an action cannot be finalized before it ends.
+const VETO_WINDOW_SECONDS: u64 = 2 * 24 * 60 * 60;
+
+/// Maximum number of options on a multiple choice proposal.
+const MAX_PROPOSAL_OPTIONS: usize = 10;
+
+/// `rate_bps` increased by `boost_bps` basis points of itself.
+fn apply_boost(rate_bps: u128, boost_bps: u128) -> u128 {
+    rate_bps * (10_000 + boost_bps) / 10_000
+}
+
+/// Crate version recorded in state by `new` and `migrate`.
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Parses a `major.minor.patch` version for ordering; missing or non-numeric
+/// parts count as 0.
+fn parse_version(version: &str) -> (u64, u64, u64) {
+    let mut parts = version.split('.').map(|part| part.parse().unwrap_or(0));
+    (
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    )
+}
+
+/// Lifecycle stage of `proposal` at `now`.
+fn proposal_status(proposal: &Proposal, now: u64) -> ProposalStatus {
+    if proposal.cancelled {
+        ProposalStatus::Cancelled
+    } else if proposal.vetoed {
+        ProposalStatus::Vetoed
+    } else if !proposal.finalized {
+        if now < proposal.deadline {
+            ProposalStatus::Active
+        } else {
+            ProposalStatus::Ended
+        }
+    } else if proposal.passed {
+        ProposalStatus::Passed
+    } else {
+        ProposalStatus::Failed
+    }
+}
+
+/// End (ns) of the window in which the admin may veto `proposal`.
+fn veto_deadline(proposal: &Proposal) -> u64 {
+    proposal.deadline + VETO_WINDOW_SECONDS * 1_000_000_000
+}
+
+/// Index of the option with the most votes (lowest index on ties),
+/// or `None` for a binary proposal.
+fn winning_option(vote_counts: &[u128]) -> Option<usize> {
+    let mut winner = None;
+    for (index, &count) in vote_counts.iter().enumerate() {
+        if winner.is_none_or(|best: usize| count > vote_counts[best]) {
+            winner = Some(index);
+        }
+    }
+    winner
+}
+
+/// NEP-297 standard name and version of this contract's own events.
+const EVENT_STANDARD: &str = "memecoin";
+const EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// NEP-297 standard name and version used for NEP-141 mint, burn and transfer events.
+const FT_EVENT_STANDARD: &str = "nep141";
+const FT_EVENT_STANDARD_VERSION: &str = "1.0.0";
+
+/// Integer square root (floor) using the Babylonian method.
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+/// State layout of the original release, before `balances` and `staked`
+/// became iterable. Only read by `migrate`.
+#[near(serializers = [borsh])]
+struct LegacyContract {
+    balances: LookupMap<AccountId, u128>,
+    total_supply: u128,
+    admin: AccountId,
+    referrals: LookupMap<AccountId, AccountId>,
+    staked: LookupMap<AccountId, u128>,
+    proposals: LookupMap<u64, LegacyProposal>,
+    next_proposal_id: u64,
+    tip_totals: LookupMap<AccountId, u128>,
+    top_tipper: Option<AccountId>,
+}
+
+/// Proposal layout of the original release. Only read by `migrate_proposals`.
+#[near(serializers = [borsh])]
+struct LegacyProposal {
+    id: u64,
+    description: String,
+    votes_for: u128,
+    votes_against: u128,
+    deadline: u64,
+    finalized: bool,
 }
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     /// Mapping from account to token balance (in yoctoNEAR)
-    balances: LookupMap<AccountId, u128>,
+    balances: UnorderedMap<AccountId, u128>,
     /// Overall total supply of tokens
     total_supply: u128,
     /// Admin account (set on initialization)
@@ -26,7 +869,7 @@ pub struct Contract {
     /// Mapping from a referred account to its referrer.
     referrals: LookupMap<AccountId, AccountId>,
     /// Mapping from account to staked tokens.
-    staked: LookupMap<AccountId, u128>,
+    staked: UnorderedMap<AccountId, u128>,
     /// Governance proposals: mapping from proposal ID to proposal details.
     proposals: LookupMap<u64, Proposal>,
     /// Next proposal ID.
@@ -35,6 +878,202 @@ pub struct Contract {
     tip_totals: LookupMap<AccountId, u128>,
     /// Account of the top tipper (based on cumulative tips given).
     top_tipper: Option<AccountId>,
+    /// Mapping from a delegating account to the account it delegated its voting power to.
+    delegations: LookupMap<AccountId, AccountId>,
+    /// Reverse index: mapping from a delegate to the accounts that delegated to it.
+    delegators: LookupMap<AccountId, Vec<AccountId>>,
+    /// Votes cast per (proposal ID, account), locking each account's weight.
+    votes: LookupMap<(u64, AccountId), VoteRecord>,
+    /// Number of accounts each referrer has referred.
+    referral_count: LookupMap<AccountId, u64>,
+    /// Cumulative referral bonuses earned per referrer.
+    referral_earnings: LookupMap<AccountId, u128>,
+    /// Timestamp (ns) up to which each staker's rewards have been accrued.
+    last_claim: LookupMap<AccountId, u64>,
+    /// Rewards accrued but left unclaimed by a partial claim.
+    unclaimed_rewards: LookupMap<AccountId, u128>,
+    /// Treasury account receiving protocol fees and penalties (defaults to the admin).
+    treasury: AccountId,
+    /// Penalty, in basis points of the unstaked amount, for unstaking early.
+    slash_bps: u16,
+    /// Minimum duration (seconds) a stake must be held to unstake without penalty.
+    min_stake_duration_seconds: u64,
+    /// Timestamp (ns) of each account's most recent stake.
+    stake_started: LookupMap<AccountId, u64>,
+    /// Minimum amount accepted by a single `stake` call.
+    min_stake: u128,
+    /// Maximum total staked per account (0 = unlimited).
+    max_stake_per_account: u128,
+    /// Annual staking reward rate, in basis points of the staked amount.
+    reward_rate_bps: u16,
+    /// Sorted index of holders keyed by (balance, account), for the holder leaderboard.
+    holder_index: TreeMap<(u128, AccountId), ()>,
+    /// Number of accounts with a non-zero liquid balance.
+    holder_count: u64,
+    /// Minimum time (seconds) between transfers from the same account (0 = disabled).
+    transfer_cooldown_seconds: u64,
+    /// Timestamp (ns) of each account's most recent transfer.
+    last_transfer: LookupMap<AccountId, u64>,
+    /// Minted NFTs: mapping from token ID to token details.
+    nft_tokens: LookupMap<u64, Nft>,
+    /// Next NFT token ID.
+    next_token_id: u64,
+    /// Maximum NFT metadata length in bytes.
+    nft_max_metadata_len: u32,
+    /// Granted roles, keyed by (account, role).
+    roles: LookupSet<(AccountId, Role)>,
+    /// Whether token operations are paused.
+    paused: bool,
+    /// Delay (seconds) between queueing a timelocked action and executing it.
+    timelock_delay_seconds: u64,
+    /// Queued timelocked actions: mapping from action ID to action details.
+    queued_actions: LookupMap<u64, QueuedAction>,
+    /// Next timelocked action ID.
+    next_action_id: u64,
+    /// Sequence number of the most recently emitted event.
+    event_seq: u64,
+    /// Voting period (seconds) applied to newly created proposals.
+    voting_period_seconds: u64,
+    /// Fee charged on withdrawals, in basis points, credited to the treasury.
+    withdraw_fee_bps: u16,
+    /// Minimum amount accepted by `withdraw`.
+    min_withdraw: u128,
+    /// Minimum deposit accepted by `mint`.
+    min_mint_deposit: u128,
+    /// Whether voting locks the balance that backed a vote until finalization.
+    vote_lock_enabled: bool,
+    /// Balance locked by votes on unfinalized proposals, per account.
+    locked_balances: LookupMap<AccountId, u128>,
+    /// Locks taken per proposal, released when it is finalized or cancelled.
+    vote_locks: LookupMap<u64, Vec<(AccountId, u128)>>,
+    /// Referrers that have their referral bonuses credited to their stake.
+    referral_to_stake: LookupMap<AccountId, bool>,
+    /// Cumulative amount of tokens burned.
+    total_burned: u128,
+    /// Cumulative amount of tokens burned from each account.
+    burned_by: LookupMap<AccountId, u128>,
+    /// Amount each (owner, spender) pair may spend from the owner's balance.
+    allowances: LookupMap<(AccountId, AccountId), u128>,
+    /// Share of every transfer burned, in basis points (0 = disabled).
+    burn_bps: u16,
+    /// Accounts with a `tip_totals` entry, in order of their first tip.
+    tippers: Vector<AccountId>,
+    /// Position of each account in `tippers`.
+    tipper_index: LookupMap<AccountId, u64>,
+    /// Price of minting an NFT, on top of its storage cost.
+    nft_mint_price: u128,
+    /// Maximum number of NFTs that can be minted (0 = unlimited).
+    nft_max_supply: u64,
+    /// NEAR paid out per token redeemed, in basis points (0 = redemption disabled).
+    redeem_rate_bps: u16,
+    /// Minimum own stake required to vote on staked-weight proposals.
+    min_stake_to_vote: u128,
+    /// Minimum stake required to use `propose_staked` (0 = disabled).
+    min_stake_to_propose: u128,
+    /// Price oracle used by `mint_with_oracle`.
+    oracle: Option<AccountId>,
+    /// Ed25519 keys accounts registered for signing `delegate_vote` and `permit` messages.
+    signing_keys: LookupMap<AccountId, Vec<u8>>,
+    /// Nonce the next signed vote of each account must carry.
+    vote_nonces: LookupMap<AccountId, u64>,
+    /// Smallest balance `mint` and `tip` may give an account that holds nothing.
+    dust_threshold: u128,
+    /// Sum of all staked balances.
+    total_staked: u128,
+    /// Number of accounts with a non-zero staked balance.
+    staker_count: u64,
+    /// Nonce the next `permit` signed by each owner must carry.
+    permit_nonces: LookupMap<AccountId, u64>,
+    /// Cap on cumulative referral bonuses (0 = uncapped).
+    max_referral_supply: u128,
+    /// Cumulative referral bonuses minted.
+    total_referral_minted: u128,
+    /// Cumulative tokens minted for deposits, via `mint` or `mint_with_oracle`.
+    total_deposit_minted: u128,
+    /// Cumulative staking rewards minted (rewards paid from the pool excluded).
+    total_reward_minted: u128,
+    /// Cumulative tokens minted through `mint_to`.
+    total_minter_minted: u128,
+    /// Wait after a proposal's deadline before it may be finalized.
+    finalization_grace_seconds: u64,
+    /// Per-account sum of stake times time staked, settled with rewards: whole
+    /// token-seconds plus a remainder in token-unit-nanoseconds.
+    stake_seconds: LookupMap<AccountId, (u128, u128)>,
+    /// Proposal IDs still stored in the original layout: the next one to convert
+    /// with `migrate_proposals`, and the end of the range.
+    legacy_proposals: (u64, u64),
+    /// Whether `tip` is open to accounts other than the admin and treasury.
+    transfers_enabled: bool,
+    /// How the quorum of new proposals is determined.
+    quorum_mode: QuorumMode,
+    /// Token IDs owned by each account.
+    tokens_per_owner: LookupMap<AccountId, UnorderedSet<u64>>,
+    /// Extra staking reward for NFT holders, in basis points of the base reward.
+    nft_boost_bps: u16,
+    /// Daily cap on NEAR paid out by `withdraw` and `redeem` (0 = unlimited).
+    daily_withdraw_limit: u128,
+    /// NEAR paid out so far in the current day, and that day's index.
+    withdrawn_today: (u128, u64),
+    /// Accounts merged away by `merge_accounts`, and where they were merged into.
+    merged_into: LookupMap<AccountId, AccountId>,
+    /// Locked stake per account, see `stake_locked`.
+    stake_locks: LookupMap<AccountId, StakeLock>,
+    /// Soulbound tokens, which `nft_transfer` rejects.
+    soulbound_tokens: LookupSet<u64>,
+    /// Stake receipts minted so far; they do not count toward `nft_max_supply`.
+    stake_receipts_minted: u64,
+    /// NFTs burned so far (stake receipts released by `unstake`).
+    nft_burned: u64,
+    /// Cap on `total_supply` for minting paths (0 = uncapped).
+    max_supply: u128,
+    /// `mint` refunds whose transfer failed, claimable with `claim_mint_refund`.
+    pending_mint_refunds: LookupMap<AccountId, u128>,
+    /// Admin tokens set aside by `set_admin_reserve`, excluded from circulation.
+    admin_reserve: u128,
+    /// Version of the code that initialized or last migrated the state.
+    version: String,
+    /// IDs of proposals that were not cancelled, for `get_proposals`.
+    proposal_ids: UnorderedSet<u64>,
+    /// IDs of proposals neither finalized nor cancelled, bounded by
+    /// `max_active_proposals`.
+    open_proposal_ids: UnorderedSet<u64>,
+    /// Halving emission schedule replacing `reward_rate_bps`, if set.
+    emission_schedule: Option<EmissionSchedule>,
+    /// Sum of `pending_mint_refunds`, kept out of `recover_unaccounted_near`.
+    total_pending_mint_refunds: u128,
+    /// Proposals neither finalized nor cancelled.
+    active_proposal_count: u64,
+    /// Limit on `active_proposal_count` enforced when creating proposals.
+    max_active_proposals: u64,
+    /// Whether `register_referral` requires the referrer to hold tokens.
+    require_referrer_holder: bool,
+    /// Endorsements, keyed by (proposal ID, account).
+    endorsements: LookupSet<(u64, AccountId)>,
+    /// Tokens held by the contract to pay staking rewards before any are minted.
+    reward_pool: u128,
+    /// Share of the transfer burn and the withdrawal fee routed into `reward_pool`.
+    fee_to_pool_bps: u16,
+    /// Token icon as a data URI, if set.
+    metadata_icon: Option<String>,
+    /// Set while a `with_lock` section runs; always false between calls.
+    locked: bool,
+    /// Floor on the voting period of new proposals; shorter periods are clamped up.
+    min_vote_duration_seconds: u64,
+    /// NEP-141 token contracts accepted by `ft_on_transfer`, with the NFT price
+    /// in each token's units.
+    accepted_tokens: UnorderedMap<AccountId, u128>,
+    /// Units of each accepted token received for NFTs and not yet withdrawn.
+    ft_proceeds: LookupMap<AccountId, u128>,
+    /// NEAR prepaid with `deposit_nft_storage` to cover the storage of NFTs
+    /// bought with accepted tokens.
+    nft_storage_credits: LookupMap<AccountId, u128>,
+    /// Sum of `nft_storage_credits`, kept out of `recover_unaccounted_near`.
+    total_nft_storage_credits: u128,
+    /// Extra weight, in basis points, for votes cast early on new proposals.
+    early_vote_bonus_bps: u16,
+    /// Leading share of a new proposal's voting period, in basis points, that
+    /// earns `early_vote_bonus_bps`.
+    early_vote_window_bps: u16,
 }
 
 #[near_bindgen]
@@ -44,15 +1083,110 @@ impl Contract {
     pub fn new() -> Self {
         assert!(!env::state_exists(), "Contract is already initialized");
         Self {
-            balances: LookupMap::new(b"b".to_vec()),
+            balances: UnorderedMap::new(b"B".to_vec()),
             total_supply: 0,
             admin: env::predecessor_account_id(),
             referrals: LookupMap::new(b"r".to_vec()),
-            staked: LookupMap::new(b"s".to_vec()),
+            staked: UnorderedMap::new(b"K".to_vec()),
             proposals: LookupMap::new(b"p".to_vec()),
             next_proposal_id: 0,
             tip_totals: LookupMap::new(b"t".to_vec()),
             top_tipper: None,
+            delegations: LookupMap::new(b"d".to_vec()),
+            delegators: LookupMap::new(b"D".to_vec()),
+            votes: LookupMap::new(b"v".to_vec()),
+            referral_count: LookupMap::new(b"c".to_vec()),
+            referral_earnings: LookupMap::new(b"e".to_vec()),
+            last_claim: LookupMap::new(b"l".to_vec()),
+            unclaimed_rewards: LookupMap::new(b"u".to_vec()),
+            treasury: env::predecessor_account_id(),
+            slash_bps: 0,
+            min_stake_duration_seconds: 0,
+            stake_started: LookupMap::new(b"S".to_vec()),
+            min_stake: 0,
+            max_stake_per_account: 0,
+            reward_rate_bps: DEFAULT_REWARD_RATE_BPS,
+            holder_index: TreeMap::new(b"h".to_vec()),
+            holder_count: 0,
+            transfer_cooldown_seconds: 0,
+            last_transfer: LookupMap::new(b"L".to_vec()),
+            nft_tokens: LookupMap::new(b"n".to_vec()),
+            next_token_id: 0,
+            nft_max_metadata_len: DEFAULT_NFT_MAX_METADATA_LEN,
+            roles: LookupSet::new(b"R".to_vec()),
+            paused: false,
+            timelock_delay_seconds: DEFAULT_TIMELOCK_DELAY_SECONDS,
+            queued_actions: LookupMap::new(b"q".to_vec()),
+            next_action_id: 0,
+            event_seq: 0,
+            voting_period_seconds: DEFAULT_VOTING_PERIOD_SECONDS,
+            withdraw_fee_bps: 0,
+            min_withdraw: 0,
+            min_mint_deposit: DEFAULT_MIN_MINT_DEPOSIT,
+            vote_lock_enabled: false,
+            locked_balances: LookupMap::new(b"k".to_vec()),
+            vote_locks: LookupMap::new(b"x".to_vec()),
+            referral_to_stake: LookupMap::new(b"o".to_vec()),
+            total_burned: 0,
+            burned_by: LookupMap::new(b"y".to_vec()),
+            allowances: LookupMap::new(b"a".to_vec()),
+            burn_bps: 0,
+            tippers: Vector::new(b"T".to_vec()),
+            tipper_index: LookupMap::new(b"F".to_vec()),
+            nft_mint_price: 0,
+            nft_max_supply: 0,
+            redeem_rate_bps: 0,
+            min_stake_to_vote: 0,
+            oracle: None,
+            signing_keys: LookupMap::new(b"g".to_vec()),
+            vote_nonces: LookupMap::new(b"N".to_vec()),
+            dust_threshold: 0,
+            total_staked: 0,
+            staker_count: 0,
+            permit_nonces: LookupMap::new(b"P".to_vec()),
+            max_referral_supply: 0,
+            total_referral_minted: 0,
+            transfers_enabled: true,
+            quorum_mode: QuorumMode::None,
+            tokens_per_owner: LookupMap::new(b"O".to_vec()),
+            nft_boost_bps: 0,
+            daily_withdraw_limit: 0,
+            withdrawn_today: (0, 0),
+            merged_into: LookupMap::new(b"M".to_vec()),
+            min_stake_to_propose: 0,
+            stake_locks: LookupMap::new(b"j".to_vec()),
+            soulbound_tokens: LookupSet::new(b"f".to_vec()),
+            stake_receipts_minted: 0,
+            nft_burned: 0,
+            max_supply: 0,
+            pending_mint_refunds: LookupMap::new(b"w".to_vec()),
+            admin_reserve: 0,
+            version: CONTRACT_VERSION.to_string(),
+            proposal_ids: UnorderedSet::new(b"i".to_vec()),
+            emission_schedule: None,
+            total_pending_mint_refunds: 0,
+            active_proposal_count: 0,
+            max_active_proposals: DEFAULT_MAX_ACTIVE_PROPOSALS,
+            require_referrer_holder: false,
+            endorsements: LookupSet::new(b"m".to_vec()),
+            reward_pool: 0,
+            fee_to_pool_bps: 0,
+            early_vote_bonus_bps: 0,
+            early_vote_window_bps: 0,
+            accepted_tokens: UnorderedMap::new(b"z".to_vec()),
+            min_vote_duration_seconds: DEFAULT_MIN_VOTE_DURATION_SECONDS,
+            metadata_icon: None,
+            locked: false,
+            total_deposit_minted: 0,
+            total_reward_minted: 0,
+            total_minter_minted: 0,
+            stake_seconds: LookupMap::new(b"A".to_vec()),
+            finalization_grace_seconds: 0,
+            legacy_proposals: (0, 0),
+            ft_proceeds: LookupMap::new(b"E".to_vec()),
+            nft_storage_credits: LookupMap::new(b"C".to_vec()),
+            total_nft_storage_credits: 0,
+            open_proposal_ids: UnorderedSet::new(b"G".to_vec()),
         }
     }
 
@@ -62,273 +1196,7561 @@ impl Contract {
 
     /// Mint tokens by attaching NEAR. The attached deposit is credited as tokens.
     /// If the caller has registered a referrer, a bonus of 1% is credited to that referrer.
+    /// Only the part of the deposit that fits under `max_supply` is credited; the
+    /// rest is refunded (all of it once the cap is reached).
     #[payable]
     pub fn mint(&mut self) {
-        let deposit: NearToken = env::attached_deposit();
-        let deposit_amount = deposit.as_yoctonear();
-        
-        // Require a minimum deposit (0.01 NEAR = 1e16 yoctoNEAR) to cover storage fees.
-        assert!(
-            deposit_amount >= 10_000_000_000_000_000,
-            "Deposit too low"
-        );
-        let caller = env::predecessor_account_id();
-        let current_balance = self.balances.get(&caller).unwrap_or(0);
-        let new_balance = current_balance + deposit_amount;
-        self.balances.insert(&caller, &new_balance);
-        self.total_supply += deposit_amount;
-
-        // Grant a 1% bonus to a registered referrer, if any.
-        if let Some(referrer) = self.referrals.get(&caller) {
-            let bonus = deposit_amount / 100;
-            let ref_balance = self.balances.get(&referrer).unwrap_or(0);
-            let new_ref_balance = ref_balance + bonus;
-            self.balances.insert(&referrer, &new_ref_balance);
-            self.total_supply += bonus;
-            env::log_str(&format!(
-                "Referral bonus: {} received {} tokens",
-                referrer, bonus
-            ));
-        }
+        self.with_lock(|this| {
+            this.assert_not_paused();
+            let deposit: NearToken = env::attached_deposit();
+            let deposit_amount = deposit.as_yoctonear();
 
-        env::log_str(&format!(
-            "Mint: {} minted {} tokens. New balance: {}. Total supply: {}",
-            caller, deposit_amount, new_balance, self.total_supply
-        ));
+            let caller = env::predecessor_account_id();
+            let credited = this.mint_credit(&caller, deposit_amount);
+            if credited > 0 {
+                this.internal_mint(&caller, credited, "deposit");
+            }
+            let refund = deposit_amount - credited;
+            if refund > 0 {
+                this.refund_mint_deposit(&caller, refund);
+                this.emit(
+                    "mint_capped",
+                    json!({
+                        "account_id": caller,
+                        "deposit": U128(deposit_amount),
+                        "credited": U128(credited),
+                        "refunded": U128(refund),
+                    }),
+                );
+            }
+        })
     }
 
-    /// Returns the token balance for a given account.
-    pub fn get_balance(&self, account: AccountId) -> U128 {
-        U128(self.balances.get(&account).unwrap_or(0))
+    /// Records a `mint` or `mint_with_oracle` refund that could not be delivered
+    /// so the account can retry it with `claim_mint_refund`.
+    #[private]
+    pub fn on_mint_refund_resolved(
+        &mut self,
+        account: AccountId,
+        amount: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) {
+        self.with_lock(|this| {
+            if result.is_ok() {
+                return;
+            }
+            let pending = this.pending_mint_refunds.get(&account).unwrap_or(0) + amount.0;
+            this.pending_mint_refunds.insert(&account, &pending);
+            this.total_pending_mint_refunds += amount.0;
+            this.emit(
+                "mint_refund_failed",
+                json!({ "account_id": account, "amount": amount }),
+            );
+        })
     }
 
-    /// Returns the overall total supply of tokens.
-    pub fn get_total_supply(&self) -> U128 {
-        U128(self.total_supply)
+    /// Retry delivering the caller's failed `mint` and `mint_with_oracle` refunds.
+    pub fn claim_mint_refund(&mut self) -> Promise {
+        self.with_lock(|this| {
+            let caller = env::predecessor_account_id();
+            let amount = this
+                .pending_mint_refunds
+                .remove(&caller)
+                .expect(errors::NO_PENDING_REFUND);
+            this.total_pending_mint_refunds -= amount;
+            this.refund_mint_deposit(&caller, amount)
+        })
     }
 
-    ////////////
-    // Tipping & Transfers
-    ////////////
+    /// Returns `account`'s undelivered `mint` and `mint_with_oracle` refunds.
+    pub fn get_pending_mint_refund(&self, account: AccountId) -> U128 {
+        U128(self.pending_mint_refunds.get(&account).unwrap_or(0))
+    }
 
-    /// Transfer tokens (tip) from the caller to another account.
-    pub fn tip(&mut self, receiver: AccountId, amount: U128) {
-        let amount: u128 = amount.into();
-        let sender = env::predecessor_account_id();
-        let sender_balance = self.balances.get(&sender).unwrap_or(0);
-        assert!(sender_balance >= amount, "Insufficient balance");
-        self.balances.insert(&sender, &(sender_balance - amount));
-        let receiver_balance = self.balances.get(&receiver).unwrap_or(0);
-        self.balances.insert(&receiver, &(receiver_balance + amount));
-        env::log_str(&format!(
-            "Tip: {} tipped {} tokens to {}",
-            sender, amount, receiver
-        ));
-
-        let total_tip = self.tip_totals.get(&sender).unwrap_or(0) + amount;
-        self.tip_totals.insert(&sender, &total_tip);
-        if let Some(current_top) = self.top_tipper.clone() {
-            let top_amount = self.tip_totals.get(&current_top).unwrap_or(0);
-            if total_tip > top_amount {
-                self.top_tipper = Some(sender.clone());
+    /// Previews what `mint` would credit for `deposit` attached by `account`,
+    /// using the same checks and bonus computation. Panics where `mint` would.
+    pub fn calculate_mint_output(&self, account: AccountId, deposit: U128) -> MintPreview {
+        let credited = self.mint_credit(&account, deposit.0);
+        let (referrer, referral_bonus) = match self.referral_bonus_for(&account, credited) {
+            Some((referrer, bonus)) => {
+                let room = self.mintable_supply().saturating_sub(credited);
+                (Some(referrer), bonus.min(room))
             }
-        } else {
-            self.top_tipper = Some(sender.clone());
+            None => (None, 0),
+        };
+        MintPreview {
+            base: U128(credited),
+            referrer,
+            referral_bonus: U128(referral_bonus),
+            net_credited: U128(credited),
+            total_minted: U128(credited + referral_bonus),
+            refunded: U128(deposit.0 - credited),
         }
     }
 
-    /// Withdraw tokens from the caller's balance.
-    /// The tokens are transferred back to the caller's wallet.
-    pub fn withdraw(&mut self, amount: U128) {
-        let amount: u128 = amount.into();
-        let sender = env::predecessor_account_id();
-        let sender_balance = self.balances.get(&sender).unwrap_or(0);
-        assert!(sender_balance >= amount, "Insufficient balance");
-        self.balances.insert(&sender, &(sender_balance - amount));
-        // Wrap the amount in NearToken before transferring.
-        Promise::new(sender.clone()).transfer(NearToken::from_yoctonear(amount));
-        env::log_str(&format!(
-            "Withdraw: {} withdrew {} tokens",
-            sender, amount
-        ));
+    /// Mint tokens priced by the configured oracle instead of 1:1. The deposit
+    /// is held while the oracle's `get_price` is queried; `on_oracle_price` then
+    /// credits `deposit * multiplier / 10^decimals` tokens in a later block, or
+    /// refunds the deposit if the oracle call fails or its price is zero or stale.
+    /// The credit never exceeds the deposit, so every token stays backed 1:1 by
+    /// NEAR for `withdraw`. Plain `mint` remains available as a fallback.
+    #[payable]
+    pub fn mint_with_oracle(&mut self) -> Promise {
+        self.with_lock(|this| {
+            this.assert_not_paused();
+            let oracle = this.oracle.clone().expect(errors::ORACLE_NOT_CONFIGURED);
+            let deposit_amount = env::attached_deposit().as_yoctonear();
+            assert!(
+                deposit_amount >= this.min_mint_deposit,
+                "{}",
+                errors::DEPOSIT_TOO_LOW
+            );
+            ext_price_oracle::ext(oracle)
+                .with_static_gas(ORACLE_VIEW_GAS)
+                .get_price()
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(ORACLE_CALLBACK_GAS)
+                        .on_oracle_price(env::predecessor_account_id(), U128(deposit_amount)),
+                )
+        })
     }
 
-    /// Burn tokens from the caller's balance, reducing total supply.
-    pub fn burn(&mut self, amount: U128) {
-        let amount: u128 = amount.into();
-        let caller = env::predecessor_account_id();
-        let current_balance = self.balances.get(&caller).unwrap_or(0);
-        assert!(
-            current_balance >= amount,
-            "Insufficient balance to burn"
-        );
-        self.balances.insert(&caller, &(current_balance - amount));
-        self.total_supply -= amount;
-        env::log_str(&format!("Burn: {} burned {} tokens", caller, amount));
+    /// Credits the tokens of a `mint_with_oracle` call, or refunds the deposit.
+    /// Returns the amount of tokens credited (0 on refund).
+    #[private]
+    pub fn on_oracle_price(
+        &mut self,
+        account: AccountId,
+        deposit: U128,
+        #[callback_result] price: Result<OraclePrice, PromiseError>,
+    ) -> U128 {
+        self.with_lock(|this| {
+            let now = env::block_timestamp();
+            let minted = match price {
+                Ok(price)
+                    if price.multiplier.0 > 0
+                        && price.decimals <= MAX_ORACLE_DECIMALS
+                        && now.saturating_sub(price.timestamp) <= MAX_ORACLE_PRICE_AGE_NS =>
+                {
+                    mul_div(
+                        deposit.0,
+                        price.multiplier.0,
+                        10u128.pow(price.decimals as u32),
+                    )
+                    .min(deposit.0)
+                }
+                _ => 0,
+            };
+            if minted == 0 || minted > this.mintable_supply() {
+                this.refund_mint_deposit(&account, deposit.0);
+                this.emit(
+                    "oracle_mint_refunded",
+                    json!({ "account_id": account, "deposit": deposit }),
+                );
+                return U128(0);
+            }
+            this.internal_mint(&account, minted, "oracle deposit");
+            U128(minted)
+        })
     }
 
-    ////////////
-    // Staking & Rewards
-    ////////////
+    /// (Admin only) Set the price oracle used by `mint_with_oracle`
+    /// (`None` disables oracle-priced minting).
+    pub fn set_oracle(&mut self, oracle: Option<AccountId>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the oracle"
+        );
+        self.emit("oracle_updated", json!({ "oracle": oracle }));
+        self.oracle = oracle;
+    }
 
-    /// Stake tokens: Moves tokens from available balance into staked balance.
-    #[payable]
-    pub fn stake(&mut self, amount: U128) {
-        let amount: u128 = amount.into();
-        let caller = env::predecessor_account_id();
-        let available = self.balances.get(&caller).unwrap_or(0);
-        assert!(available >= amount, "Insufficient balance to stake");
-        self.balances.insert(&caller, &(available - amount));
-        let current_staked = self.staked.get(&caller).unwrap_or(0);
-        self.staked.insert(&caller, &(current_staked + amount));
-        env::log_str(&format!("Stake: {} staked {} tokens", caller, amount));
+    /// Returns the price oracle used by `mint_with_oracle`, if any.
+    pub fn get_oracle(&self) -> Option<AccountId> {
+        self.oracle.clone()
     }
 
-    /// Unstake tokens: Moves tokens from staked balance back to available balance.
-    pub fn unstake(&mut self, amount: U128) {
+    /// (Minter only) Credit `amount` newly issued tokens to `account` without an
+    /// attached deposit, e.g. for distributions funded off-chain.
+    /// This is an inflationary power: hand the Minter role only to a timelocked
+    /// or governed account.
+    pub fn mint_to(&mut self, account: AccountId, amount: U128) {
+        self.assert_not_paused();
+        self.assert_role(Role::Minter);
         let amount: u128 = amount.into();
-        let caller = env::predecessor_account_id();
-        let current_staked = self.staked.get(&caller).unwrap_or(0);
+        assert!(amount > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
         assert!(
-            current_staked >= amount,
-            "Insufficient staked balance"
+            amount <= self.mintable_supply(),
+            "{}",
+            errors::MAX_SUPPLY_EXCEEDED
+        );
+        let balance = self.balance_of_internal(&account);
+        self.set_balance(&account, balance + amount);
+        self.total_supply += amount;
+        self.total_minter_minted += amount;
+        self.emit_ft(
+            "ft_mint",
+            json!({ "owner_id": account, "amount": U128(amount), "memo": "mint_to" }),
+        );
+        self.emit(
+            "mint_to",
+            json!({
+                "minter_id": env::predecessor_account_id(),
+                "account_id": account,
+                "amount": U128(amount),
+            }),
         );
-        self.staked.insert(&caller, &(current_staked - amount));
-        let available = self.balances.get(&caller).unwrap_or(0);
-        self.balances.insert(&caller, &(available + amount));
-        env::log_str(&format!("Unstake: {} unstaked {} tokens", caller, amount));
     }
 
-    /// Claim staking rewards.
-    /// (For demonstration, rewards are set at 5% of the staked amount.)
-    pub fn claim_rewards(&mut self) {
-        let caller = env::predecessor_account_id();
-        let staked_amount = self.staked.get(&caller).unwrap_or(0);
-        assert!(staked_amount > 0, "No staked tokens");
-        let reward = staked_amount * 5 / 100;
-        let available = self.balances.get(&caller).unwrap_or(0);
-        self.balances.insert(&caller, &(available + reward));
-        self.total_supply += reward;
-        env::log_str(&format!(
-            "Claim Rewards: {} claimed {} tokens as reward",
-            caller, reward
-        ));
+    /// (Admin only) Set the minimum deposit accepted by `mint`.
+    pub fn set_min_mint_deposit(&mut self, amount: U128) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the minimum mint deposit"
+        );
+        self.min_mint_deposit = amount.0;
+        self.emit("min_mint_deposit_updated", json!({ "amount": amount }));
     }
 
-    ////////////
-    // Referral System
-    ////////////
-
-    /// Register a referrer for the caller.
-    /// (A caller can register a referrer once; future mints will grant a bonus to that referrer.)
-    pub fn register_referral(&mut self, referrer: AccountId) {
-        let caller = env::predecessor_account_id();
-        assert_ne!(caller, referrer, "Cannot refer yourself");
-        assert!(
-            self.referrals.get(&caller).is_none(),
-            "Referral already registered"
+    /// (Admin only) Set the smallest balance `mint` and `tip` may create for an
+    /// account that currently holds nothing (0 disables the check). Existing
+    /// holders can still receive any amount.
+    pub fn set_dust_threshold(&mut self, amount: U128) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the dust threshold"
         );
-        self.referrals.insert(&caller, &referrer);
-        env::log_str(&format!(
-            "Referral: {} registered referrer {}",
-            caller, referrer
-        ));
+        self.dust_threshold = amount.0;
+        self.emit("dust_threshold_updated", json!({ "amount": amount }));
     }
 
-    ////////////
-    // Governance & Voting
-    ////////////
+    /// Returns the dust threshold for new holders.
+    pub fn get_dust_threshold(&self) -> U128 {
+        U128(self.dust_threshold)
+    }
 
-    /// (Admin only) Create a new governance proposal.
-    /// (For simplicity, each proposal is active for 7 days.)
-    #[payable]
-    pub fn propose(&mut self, description: String) {
-        let caller = env::predecessor_account_id();
-        assert_eq!(caller, self.admin, "Only admin can create proposals");
-        let proposal = Proposal {
-            id: self.next_proposal_id,
-            description,
-            votes_for: 0,
-            votes_against: 0,
-            // 7 days in nanoseconds
-            deadline: env::block_timestamp() + 7 * 24 * 60 * 60 * 1_000_000_000,
-            finalized: false,
-        };
-        self.proposals.insert(&self.next_proposal_id, &proposal);
-        env::log_str(&format!(
-            "Governance: Proposal {} created",
-            self.next_proposal_id
-        ));
-        self.next_proposal_id += 1;
+    /// Returns the minimum deposit accepted by `mint`.
+    pub fn get_min_mint_deposit(&self) -> U128 {
+        U128(self.min_mint_deposit)
     }
 
-    /// Vote on an existing proposal.
-    /// (Voting power is based on the caller's current token balance.)
-    pub fn vote(&mut self, proposal_id: u64, support: bool) {
-        let caller = env::predecessor_account_id();
-        let voter_balance = self.balances.get(&caller).unwrap_or(0);
-        assert!(voter_balance > 0, "No voting power");
-        let mut proposal = self.proposals.get(&proposal_id).expect("Proposal not found");
+    /// (Admin only) Cap the total supply (0 = uncapped). Deposit and oracle
+    /// mints, `mint_to` and referral bonuses stay within the cap; staking
+    /// rewards are minted outside it. Cannot be set below the current supply.
+    pub fn set_max_supply(&mut self, max_supply: U128) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the max supply"
+        );
         assert!(
-            env::block_timestamp() < proposal.deadline,
-            "Voting period has ended"
+            max_supply.0 == 0 || max_supply.0 >= self.total_supply,
+            "Max supply below current supply"
         );
-        if support {
-            proposal.votes_for += voter_balance;
-        } else {
-            proposal.votes_against += voter_balance;
-        }
-        self.proposals.insert(&proposal_id, &proposal);
-        env::log_str(&format!(
-            "Governance: {} voted on proposal {}",
-            caller, proposal_id
-        ));
+        self.max_supply = max_supply.0;
+        self.emit("max_supply_updated", json!({ "max_supply": max_supply }));
     }
 
-    /// Finalize a proposal (admin only) once its voting deadline has passed.
-    pub fn finalize_proposal(&mut self, proposal_id: u64) {
-        let caller = env::predecessor_account_id();
-        assert_eq!(caller, self.admin, "Only admin can finalize proposals");
-        let mut proposal = self.proposals.get(&proposal_id).expect("Proposal not found");
-        assert!(
-            env::block_timestamp() >= proposal.deadline,
-            "Voting period not ended"
+    /// Returns the supply cap (0 = uncapped).
+    pub fn get_max_supply(&self) -> U128 {
+        U128(self.max_supply)
+    }
+
+    /// Returns the token balance for a given account.
+    pub fn get_balance(&self, account: AccountId) -> U128 {
+        U128(self.balance_of_internal(&account))
+    }
+
+    /// Returns whether the contract already holds a balance or stake entry for an
+    /// account. Receiving tokens does not require storage registration (the
+    /// contract covers new entries), so `tip` never fails on the receiver; a
+    /// sender fails with `E64` if unregistered and `E3` if its balance is short.
+    pub fn is_registered(&self, account_id: AccountId) -> bool {
+        self.has_account(&account_id)
+    }
+
+    /// Returns the token balance of each given account (0 for unknown accounts), in order.
+    /// At most `MAX_BATCH_QUERY` (100) accounts may be queried per call.
+    pub fn get_balances(&self, accounts: Vec<AccountId>) -> Vec<U128> {
+        assert!(
+            accounts.len() <= MAX_BATCH_QUERY,
+            "{}",
+            errors::TOO_MANY_ACCOUNTS
         );
-        proposal.finalized = true;
-        self.proposals.insert(&proposal_id, &proposal);
-        env::log_str(&format!(
-            "Governance: Proposal {} finalized. Votes for: {}, Votes against: {}",
-            proposal_id, proposal.votes_for, proposal.votes_against
-        ));
+        accounts
+            .iter()
+            .map(|account| U128(self.balance_of_internal(account)))
+            .collect()
+    }
+
+    /// Returns the staked balance of each given account (0 for unknown accounts), in order.
+    /// At most `MAX_BATCH_QUERY` (100) accounts may be queried per call.
+    pub fn get_staked_batch(&self, accounts: Vec<AccountId>) -> Vec<U128> {
+        assert!(
+            accounts.len() <= MAX_BATCH_QUERY,
+            "{}",
+            errors::TOO_MANY_ACCOUNTS
+        );
+        accounts
+            .iter()
+            .map(|account| U128(self.staked.get(account).unwrap_or(0)))
+            .collect()
+    }
+
+    /// Returns the overall total supply of tokens.
+    pub fn get_total_supply(&self) -> U128 {
+        U128(self.total_supply)
+    }
+
+    /// Returns the circulating supply: total supply minus the treasury's balance
+    /// and the admin reserve.
+    pub fn get_circulating_supply(&self) -> U128 {
+        U128(self.circulating_supply())
+    }
+
+    /// Returns an account's balances, rewards, and referral data in a single call.
+    pub fn get_account_summary(&self, account: AccountId) -> AccountSummary {
+        AccountSummary {
+            balance: U128(self.balance_of_internal(&account)),
+            staked: U128(self.staked.get(&account).unwrap_or(0)),
+            pending_rewards: U128(self.accrued(&account, env::block_timestamp())),
+            referrer: self.referrals.get(&account),
+            tip_total: U128(self.tip_totals.get(&account).unwrap_or(0)),
+            referral_count: self.referral_count.get(&account).unwrap_or(0),
+        }
+    }
+
+    /// Returns how an account's tokens split into liquid, staked and vote-locked
+    /// amounts, and how much of them it can move right now.
+    pub fn get_effective_balance(&self, account: AccountId) -> BalanceBreakdown {
+        let liquid = self.balance_of_internal(&account);
+        let vote_locked = self.locked_balances.get(&account).unwrap_or(0).min(liquid);
+        BalanceBreakdown {
+            liquid: U128(liquid),
+            staked: U128(self.staked.get(&account).unwrap_or(0)),
+            vote_locked: U128(vote_locked),
+            spendable: U128(liquid - vote_locked),
+        }
+    }
+
+    /// Returns the number of accounts holding a non-zero liquid balance.
+    pub fn get_holder_count(&self) -> u64 {
+        self.holder_count
+    }
+
+    /// Returns holders ordered by balance, largest first, starting at `from_index`.
+    /// `limit` is capped at `MAX_PAGE_LIMIT`.
+    pub fn get_top_holders(&self, from_index: u64, limit: u64) -> Vec<(AccountId, U128)> {
+        self.holder_index
+            .iter_rev()
+            .skip(from_index as usize)
+            .take(limit.min(MAX_PAGE_LIMIT) as usize)
+            .map(|((balance, account), _)| (account, U128(balance)))
+            .collect()
     }
 
     ////////////
-    // NFT Minting Stub
+    // Tipping & Transfers
     ////////////
 
-    /// NFT minting stub.
-    /// (This function logs an NFT mint event along with provided metadata.)
-    #[payable]
-    pub fn nft_mint(&mut self, metadata: String) {
-        let deposit: NearToken = env::attached_deposit();
-        let deposit_amount = deposit.as_yoctonear();
+    /// Transfer tokens (tip) from the caller to another account.
+    /// Subject to the per-account transfer cooldown, if enabled. `burn_bps` of
+    /// the amount (rounded up) is burned and the receiver gets the rest, so
+    /// the sender is always debited exactly `amount`.
+    pub fn tip(&mut self, receiver: AccountId, amount: U128) {
+        self.assert_not_paused();
+        let amount: u128 = amount.into();
+        let sender = env::predecessor_account_id();
+        assert_ne!(sender, receiver, "{}", errors::CANNOT_TIP_SELF);
+        assert!(amount > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+        self.assert_transfers_enabled(&sender);
+        self.enforce_transfer_cooldown(&sender);
+        let sender_balance = self.require_account(&sender);
+        assert!(sender_balance >= amount, "{}", errors::INSUFFICIENT_BALANCE);
+        self.assert_unlocked(&sender, sender_balance, amount);
+        self.set_balance(&sender, sender_balance - amount);
+        // Burns round up: the sender is debited exactly `amount` either way.
+        let burned = apply_bps(amount, self.burn_bps, true);
+        let received = amount - burned;
+        self.assert_not_dust(&receiver, received);
+        let receiver_balance = self.balance_of_internal(&receiver);
+        self.set_balance(&receiver, receiver_balance + received);
+        self.emit_ft(
+            "ft_transfer",
+            json!({
+                "old_owner_id": sender,
+                "new_owner_id": receiver,
+                "amount": U128(received),
+                "memo": "tip",
+            }),
+        );
+        let burned = burned - self.fund_reward_pool(burned, "transfer burn");
+        if burned > 0 {
+            self.total_supply -= burned;
+            self.record_burn(&sender, burned);
+            self.emit_ft(
+                "ft_burn",
+                json!({ "owner_id": sender, "amount": U128(burned), "memo": "transfer burn" }),
+            );
+        }
+
+        let previous_total = self.tip_totals.get(&sender);
+        if previous_total.is_none() {
+            self.tipper_index.insert(&sender, &self.tippers.len());
+            self.tippers.push(&sender);
+        }
+        let total_tip = previous_total.unwrap_or(0) + amount;
+        self.tip_totals.insert(&sender, &total_tip);
+        self.update_top_tipper(&sender, total_tip);
+    }
+
+    /// Transfer `amounts[i]` of the caller's tokens to `receivers[i]`, logging a
+    /// single `ft_transfer` event for the whole batch. Subject to the same checks
+    /// and transfer burn as `tip` (the cooldown applies once per call), but
+    /// airdrops are not counted as tips. At most `MAX_BATCH_QUERY` receivers per
+    /// call.
+    pub fn airdrop(&mut self, receivers: Vec<AccountId>, amounts: Vec<U128>) {
+        self.assert_not_paused();
+        assert_eq!(
+            receivers.len(),
+            amounts.len(),
+            "{}",
+            errors::LENGTH_MISMATCH
+        );
+        assert!(
+            receivers.len() <= MAX_BATCH_QUERY,
+            "{}",
+            errors::TOO_MANY_ACCOUNTS
+        );
+        let sender = env::predecessor_account_id();
+        self.assert_transfers_enabled(&sender);
+        self.enforce_transfer_cooldown(&sender);
+        let mut total: u128 = 0;
+        for (receiver, amount) in receivers.iter().zip(&amounts) {
+            assert_ne!(&sender, receiver, "{}", errors::CANNOT_TIP_SELF);
+            assert!(amount.0 > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+            total = total
+                .checked_add(amount.0)
+                .expect(errors::INSUFFICIENT_BALANCE);
+        }
+        let sender_balance = self.require_account(&sender);
+        assert!(sender_balance >= total, "{}", errors::INSUFFICIENT_BALANCE);
+        self.assert_unlocked(&sender, sender_balance, total);
+        self.set_balance(&sender, sender_balance - total);
+        let mut transfers = Vec::with_capacity(receivers.len());
+        let mut total_burned = 0;
+        for (receiver, amount) in receivers.iter().zip(&amounts) {
+            // Each entry is burned like a separate `tip`.
+            let burned = apply_bps(amount.0, self.burn_bps, true);
+            let received = amount.0 - burned;
+            total_burned += burned;
+            self.assert_not_dust(receiver, received);
+            let receiver_balance = self.balance_of_internal(receiver);
+            self.set_balance(receiver, receiver_balance + received);
+            transfers.push(json!({
+                "old_owner_id": sender,
+                "new_owner_id": receiver,
+                "amount": U128(received),
+                "memo": "airdrop",
+            }));
+        }
+        self.emit_ft_batch("ft_transfer", transfers);
+        let burned = total_burned - self.fund_reward_pool(total_burned, "transfer burn");
+        if burned > 0 {
+            self.total_supply -= burned;
+            self.record_burn(&sender, burned);
+            self.emit_ft(
+                "ft_burn",
+                json!({ "owner_id": sender, "amount": U128(burned), "memo": "transfer burn" }),
+            );
+        }
+    }
+
+    /// (Admin only) Set the minimum time between transfers from the same account
+    /// (0 disables the cooldown). The admin and treasury are exempt.
+    pub fn set_transfer_cooldown(&mut self, seconds: u64) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the transfer cooldown"
+        );
+        self.transfer_cooldown_seconds = seconds;
+        self.emit("transfer_cooldown_updated", json!({ "seconds": seconds }));
+    }
+
+    /// (Admin only) Set the share of every transfer that is burned, in basis
+    /// points (0 disables it). Capped at `MAX_BURN_BPS`.
+    pub fn set_burn_bps(&mut self, bps: u16) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the transfer burn"
+        );
+        self.internal_set_burn_bps(bps);
+    }
+
+    /// Returns the share of every transfer that is burned, in basis points.
+    pub fn get_burn_bps(&self) -> u16 {
+        self.burn_bps
+    }
+
+    /// Returns the transfer cooldown in seconds (0 = disabled).
+    pub fn get_transfer_cooldown(&self) -> u64 {
+        self.transfer_cooldown_seconds
+    }
+
+    /// Returns the earliest timestamp (ns) at which an account may transfer again.
+    /// Returns 0 if the account is not currently restricted.
+    pub fn get_next_transfer_time(&self, account: AccountId) -> u64 {
+        if self.is_cooldown_exempt(&account) {
+            return 0;
+        }
+        match self.last_transfer.get(&account) {
+            Some(last) => last + self.transfer_cooldown_seconds * 1_000_000_000,
+            None => 0,
+        }
+    }
+
+    /// Withdraw tokens from the caller's balance.
+    /// The tokens are transferred back to the caller's wallet, minus a withdrawal fee
+    /// that is credited to the treasury. Amounts below `min_withdraw` are rejected.
+    /// The fee is only collected once the transfer succeeds; if it fails, the full
+    /// amount is credited back.
+    pub fn withdraw(&mut self, amount: U128) -> Promise {
+        self.with_lock(|this| {
+            this.assert_not_paused();
+            let amount: u128 = amount.into();
+            assert!(amount > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+            assert!(
+                amount >= this.min_withdraw,
+                "{}",
+                errors::WITHDRAWAL_BELOW_MINIMUM
+            );
+            let sender = env::predecessor_account_id();
+            let sender_balance = this.require_account(&sender);
+            assert!(sender_balance >= amount, "{}", errors::INSUFFICIENT_BALANCE);
+            this.assert_unlocked(&sender, sender_balance, amount);
+            this.set_balance(&sender, sender_balance - amount);
+            // Fees round up, in the treasury's favor; `net` is the exact remainder.
+            let fee = apply_bps(amount, this.withdraw_fee_bps, true);
+            let net = amount - fee;
+            let day = this.record_outflow(net);
+            this.emit_with_balances(
+                "withdraw",
+                json!({
+                    "account_id": sender,
+                    "amount": U128(amount),
+                    "fee": U128(fee),
+                    "net": U128(net),
+                }),
+            );
+            this.transfer_withdrawal(sender, amount, fee, day)
+        })
+    }
+
+    /// Withdraw the caller's whole liquid balance, except what open votes lock,
+    /// under the same fee, minimum and daily limit rules as `withdraw`. Staked
+    /// tokens are not touched. The fee is only collected once the transfer
+    /// succeeds; if it fails, the full amount is credited back.
+    pub fn withdraw_all(&mut self) -> Promise {
+        self.with_lock(|this| {
+            this.assert_not_paused();
+            let sender = env::predecessor_account_id();
+            let balance = this.require_account(&sender);
+            let amount = balance.saturating_sub(this.locked_balances.get(&sender).unwrap_or(0));
+            assert!(amount > 0, "{}", errors::INSUFFICIENT_BALANCE);
+            assert!(
+                amount >= this.min_withdraw,
+                "{}",
+                errors::WITHDRAWAL_BELOW_MINIMUM
+            );
+            this.set_balance(&sender, balance - amount);
+            let fee = apply_bps(amount, this.withdraw_fee_bps, true);
+            let net = amount - fee;
+            let day = this.record_outflow(net);
+            this.emit_with_balances(
+                "withdraw_all",
+                json!({
+                    "account_id": sender,
+                    "amount": U128(amount),
+                    "fee": U128(fee),
+                    "net": U128(net),
+                }),
+            );
+            this.transfer_withdrawal(sender, amount, fee, day)
+        })
+    }
+
+    /// Collects the fee of a successful `withdraw` or `withdraw_all`, or credits
+    /// the full `amount` back to `account` if the transfer failed. A failed
+    /// transfer no longer counts against the daily limit of `day`.
+    #[private]
+    pub fn on_withdraw_resolved(
+        &mut self,
+        account: AccountId,
+        amount: U128,
+        fee: U128,
+        day: u64,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) {
+        self.with_lock(|this| {
+            if result.is_ok() {
+                this.credit_withdraw_fee(fee.0);
+                return;
+            }
+            this.reverse_outflow(amount.0 - fee.0, day);
+            let balance = this.balance_of_internal(&account);
+            this.set_balance(&account, balance + amount.0);
+            this.emit(
+                "withdraw_refunded",
+                json!({ "account_id": account, "amount": amount }),
+            );
+        })
+    }
+
+    /// (Admin only) Configure the withdrawal fee (basis points, credited to the
+    /// treasury) and the minimum withdrawal amount.
+    pub fn set_withdraw_config(&mut self, fee_bps: u16, min_withdraw: U128) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can configure withdrawals"
+        );
+        assert!(fee_bps <= MAX_WITHDRAW_FEE_BPS, "Withdrawal fee too high");
+        self.withdraw_fee_bps = fee_bps;
+        self.min_withdraw = min_withdraw.0;
+        self.emit(
+            "withdraw_config_updated",
+            json!({ "fee_bps": fee_bps, "min_withdraw": min_withdraw }),
+        );
+    }
+
+    /// (Admin only) Lower the daily cap on NEAR paid out by `withdraw` and
+    /// `redeem`. Raising or removing it (0 = unlimited) must go through
+    /// `TimelockedAction::SetDailyWithdrawLimit`.
+    pub fn set_daily_withdraw_limit(&mut self, limit: U128) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the daily withdrawal limit"
+        );
         assert!(
-            deposit_amount > 1,
-            "Attached deposit too low for NFT minting"
+            limit.0 > 0 && (self.daily_withdraw_limit == 0 || limit.0 <= self.daily_withdraw_limit),
+            "Raising the daily withdrawal limit requires a timelocked action"
+        );
+        self.internal_set_daily_withdraw_limit(limit.0);
+    }
+
+    /// Returns the daily cap on NEAR paid out by `withdraw` and `redeem` (0 = unlimited).
+    pub fn get_daily_withdraw_limit(&self) -> U128 {
+        U128(self.daily_withdraw_limit)
+    }
+
+    /// Returns how much NEAR can still be paid out today, or `None` if unlimited.
+    /// Days are UTC days of the block timestamp.
+    pub fn get_remaining_daily_withdraw(&self) -> Option<U128> {
+        if self.daily_withdraw_limit == 0 {
+            return None;
+        }
+        let (withdrawn, bucket) = self.withdrawn_today;
+        let withdrawn = if bucket == env::block_timestamp() / DAY_NS {
+            withdrawn
+        } else {
+            0
+        };
+        Some(U128(self.daily_withdraw_limit.saturating_sub(withdrawn)))
+    }
+
+    /// Returns the withdrawal fee in basis points.
+    pub fn get_withdraw_fee_bps(&self) -> u16 {
+        self.withdraw_fee_bps
+    }
+
+    /// Returns the minimum withdrawal amount.
+    pub fn get_min_withdraw(&self) -> U128 {
+        U128(self.min_withdraw)
+    }
+
+    /// Burn `amount` of the caller's tokens and pay out
+    /// `amount * redeem_rate_bps / 10_000` yoctoNEAR from the contract balance.
+    /// Unlike `withdraw`, this reduces total supply. If the transfer fails the
+    /// tokens are credited back. Panics without changing anything if the payout
+    /// is below `min_near_out`, e.g. because the rate was lowered after signing.
+    pub fn redeem(&mut self, amount: U128, min_near_out: U128) -> Promise {
+        self.with_lock(|this| {
+            this.assert_not_paused();
+            assert!(this.redeem_rate_bps > 0, "{}", errors::REDEMPTION_DISABLED);
+            let amount: u128 = amount.into();
+            assert!(amount > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+            let caller = env::predecessor_account_id();
+            let balance = this.require_account(&caller);
+            assert!(balance >= amount, "{}", errors::INSUFFICIENT_BALANCE);
+            this.assert_unlocked(&caller, balance, amount);
+            // Payouts round down so the contract never sends more NEAR than the rate.
+            let payout = apply_bps(amount, this.redeem_rate_bps, false);
+            assert!(payout > 0, "{}", errors::REDEMPTION_TOO_SMALL);
+            assert!(
+                payout >= min_near_out.0,
+                "{}",
+                errors::REDEMPTION_BELOW_MINIMUM
+            );
+            assert!(
+                env::account_balance().as_yoctonear() >= payout,
+                "{}",
+                errors::REDEMPTION_NOT_COVERED
+            );
+            let day = this.record_outflow(payout);
+            this.set_balance(&caller, balance - amount);
+            this.total_supply -= amount;
+            this.record_burn(&caller, amount);
+            this.emit_ft(
+                "ft_burn",
+                json!({ "owner_id": caller, "amount": U128(amount), "memo": "redeem" }),
+            );
+            this.emit(
+                "redeem",
+                json!({ "account_id": caller, "amount": U128(amount), "payout": U128(payout) }),
+            );
+            Promise::new(caller.clone())
+                .transfer(NearToken::from_yoctonear(payout))
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(REDEEM_CALLBACK_GAS)
+                        .on_redeem_resolved(caller, U128(amount), U128(payout), day),
+                )
+        })
+    }
+
+    /// Credits the tokens of a failed `redeem` back to `account` and removes its
+    /// `payout` from the daily withdrawal total of `day`.
+    #[private]
+    pub fn on_redeem_resolved(
+        &mut self,
+        account: AccountId,
+        amount: U128,
+        payout: U128,
+        day: u64,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) {
+        self.with_lock(|this| {
+            if result.is_ok() {
+                return;
+            }
+            this.reverse_outflow(payout.0, day);
+            let amount: u128 = amount.into();
+            let balance = this.balance_of_internal(&account);
+            this.set_balance(&account, balance + amount);
+            this.total_supply += amount;
+            this.total_burned -= amount;
+            let burned = this.burned_by.get(&account).unwrap_or(0) - amount;
+            this.burned_by.insert(&account, &burned);
+            this.emit_ft(
+                "ft_mint",
+                json!({ "owner_id": account, "amount": U128(amount), "memo": "redeem refund" }),
+            );
+        })
+    }
+
+    /// (Admin only) Set the redemption rate in basis points of NEAR per token
+    /// (0 disables redemption). Capped at `MAX_REDEEM_RATE_BPS`.
+    pub fn set_redeem_rate(&mut self, bps: u16) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the redemption rate"
         );
+        assert!(bps <= MAX_REDEEM_RATE_BPS, "Redemption rate too high");
+        self.redeem_rate_bps = bps;
+        self.emit("redeem_rate_updated", json!({ "bps": bps }));
+    }
+
+    /// Returns the redemption rate in basis points (0 = disabled).
+    pub fn get_redeem_rate(&self) -> u16 {
+        self.redeem_rate_bps
+    }
+
+    /// Burn tokens from the caller's balance, reducing total supply.
+    pub fn burn(&mut self, amount: U128) {
+        self.assert_not_paused();
+        let amount: u128 = amount.into();
+        assert!(amount > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
         let caller = env::predecessor_account_id();
-        env::log_str(&format!(
-            "NFT Mint: {} minted an NFT with metadata: {}",
-            caller, metadata
-        ));
+        let current_balance = self.require_account(&caller);
+        assert!(
+            current_balance >= amount,
+            "{}",
+            errors::INSUFFICIENT_BALANCE_TO_BURN
+        );
+        self.set_balance(&caller, current_balance - amount);
+        self.total_supply -= amount;
+        self.record_burn(&caller, amount);
+        self.emit_ft(
+            "ft_burn",
+            json!({ "owner_id": caller, "amount": U128(amount) }),
+        );
+    }
+
+    /// (Admin only) Burn tokens from a specific account, reducing total supply.
+    /// Intended for clawing back tokens from exploited accounts or correcting mistakes.
+    /// Note: this lets the admin destroy any holder's tokens, which is a
+    /// significant centralization vector, so the burn is queued as
+    /// `TimelockedAction::AdminBurn` and holders can react before it takes
+    /// effect. Returns the action ID.
+    pub fn admin_burn(&mut self, account: AccountId, amount: U128) -> u64 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can force-burn tokens"
+        );
+        self.internal_queue_action(TimelockedAction::AdminBurn { account, amount })
+    }
+
+    /// (Admin only) Burn `amount` of the treasury's balance, e.g. after a
+    /// buyback. Can also be done by a passed `ProposalAction::BurnTreasury`.
+    pub fn burn_treasury(&mut self, amount: U128) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can burn treasury tokens"
+        );
+        self.internal_burn_treasury(amount.0);
+    }
+
+    /// (Admin only) Move every position of `from` into `to`, e.g. after a user
+    /// lost access to `from` and proved ownership of `to` off-chain. Balances,
+    /// stakes, rewards, tip totals and referral statistics are added to any
+    /// positions `to` already has, and future referral bonuses for accounts
+    /// referred by `from` are paid to `to`; if `to` itself was referred by
+    /// `from`, that referral is dropped. NFTs, allowances and delegations are
+    /// not moved. The merge is queued as `TimelockedAction::MergeAccounts`;
+    /// returns the action ID.
+    pub fn merge_accounts(&mut self, from: AccountId, to: AccountId) -> u64 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can merge accounts"
+        );
+        self.internal_queue_action(TimelockedAction::MergeAccounts { from, to })
+    }
+
+    /// Returns the cumulative amount of tokens burned.
+    pub fn get_total_burned(&self) -> U128 {
+        U128(self.total_burned)
+    }
+
+    /// Returns the cumulative amount of tokens burned from `account`.
+    pub fn get_burned_by(&self, account: AccountId) -> U128 {
+        U128(self.burned_by.get(&account).unwrap_or(0))
+    }
+
+    /// Allow `spender` to spend up to `amount` of the caller's tokens, replacing
+    /// any previous allowance. An amount of 0 revokes the allowance.
+    pub fn approve(&mut self, spender: AccountId, amount: U128) {
+        let owner = env::predecessor_account_id();
+        assert_ne!(owner, spender, "{}", errors::CANNOT_APPROVE_SELF);
+        self.set_allowance(&owner, &spender, amount.0);
+        self.emit(
+            "approve",
+            json!({ "owner_id": owner, "spender_id": spender, "amount": amount }),
+        );
+    }
+
+    /// Set `spender`'s allowance over `owner`'s balance from an approval `owner`
+    /// signed off-chain with its registered signing key (see
+    /// `register_signing_key`), so anyone can relay it. `public_key` must be that
+    /// registered key. The signed message is
+    /// `"<contract>:permit:<owner>:<spender>:<amount>:<nonce>:<deadline>"`, where
+    /// `nonce` is `get_permit_nonce(owner)` and `deadline` is a block timestamp in
+    /// nanoseconds after which the permit is rejected.
+    pub fn permit(
+        &mut self,
+        owner: AccountId,
+        spender: AccountId,
+        amount: U128,
+        deadline: u64,
+        signature: Vec<u8>,
+        public_key: Vec<u8>,
+    ) {
+        assert!(
+            env::block_timestamp() <= deadline,
+            "{}",
+            errors::PERMIT_EXPIRED
+        );
+        assert_ne!(owner, spender, "{}", errors::CANNOT_APPROVE_SELF);
+        assert!(
+            self.signing_keys.get(&owner) == Some(public_key),
+            "{}",
+            errors::WRONG_SIGNING_KEY
+        );
+        let nonce = self.permit_nonces.get(&owner).unwrap_or(0);
+        let message = format!(
+            "{}:permit:{}:{}:{}:{}:{}",
+            env::current_account_id(),
+            owner,
+            spender,
+            amount.0,
+            nonce,
+            deadline
+        );
+        self.assert_signed_by(&owner, &message, signature);
+        self.permit_nonces.insert(&owner, &(nonce + 1));
+        self.set_allowance(&owner, &spender, amount.0);
+        self.emit(
+            "approve",
+            json!({ "owner_id": owner, "spender_id": spender, "amount": amount, "permit": true }),
+        );
+    }
+
+    /// Returns the nonce the next `permit` signed by `owner` must use.
+    pub fn get_permit_nonce(&self, owner: AccountId) -> u64 {
+        self.permit_nonces.get(&owner).unwrap_or(0)
+    }
+
+    /// Returns how much `spender` may still spend from `owner`'s balance.
+    pub fn get_allowance(&self, owner: AccountId, spender: AccountId) -> U128 {
+        U128(self.allowances.get(&(owner, spender)).unwrap_or(0))
+    }
+
+    /// Burn `amount` of `owner`'s tokens out of the caller's allowance,
+    /// reducing total supply.
+    pub fn burn_from(&mut self, owner: AccountId, amount: U128) {
+        self.assert_not_paused();
+        let amount: u128 = amount.into();
+        assert!(amount > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+        let spender = env::predecessor_account_id();
+        let allowance = self
+            .allowances
+            .get(&(owner.clone(), spender.clone()))
+            .unwrap_or(0);
+        assert!(allowance >= amount, "{}", errors::INSUFFICIENT_ALLOWANCE);
+        let current_balance = self.require_account(&owner);
+        assert!(
+            current_balance >= amount,
+            "{}",
+            errors::INSUFFICIENT_BALANCE_TO_BURN
+        );
+        self.set_allowance(&owner, &spender, allowance - amount);
+        self.set_balance(&owner, current_balance - amount);
+        self.total_supply -= amount;
+        self.record_burn(&owner, amount);
+        self.emit_ft(
+            "ft_burn",
+            json!({ "owner_id": owner, "amount": U128(amount), "memo": "burn_from" }),
+        );
     }
 
     ////////////
-    // Leaderboard
+    // Staking & Rewards
     ////////////
 
-    /// Returns the account of the top tipper (i.e. the account that has tipped the most cumulatively).
-    pub fn get_top_tipper(&self) -> Option<AccountId> {
-        self.top_tipper.clone()
+    /// Stake tokens: Moves tokens from available balance into staked balance.
+    #[payable]
+    pub fn stake(&mut self, amount: U128) {
+        self.assert_not_paused();
+        let amount: u128 = amount.into();
+        let caller = env::predecessor_account_id();
+        self.internal_stake(&caller, amount);
+        self.emit_with_balances(
+            "stake",
+            json!({ "account_id": caller, "amount": U128(amount) }),
+        );
+    }
+
+    /// Stake tokens and lock them for `lock_seconds`. The caller receives a
+    /// soulbound receipt NFT describing the locked amount and unlock time.
+    /// Locking again adds to the existing lock, keeps the later unlock time and
+    /// updates the receipt. Locked stake cannot be unstaked before it unlocks;
+    /// the first `unstake` after that releases the lock and burns the receipt.
+    pub fn stake_locked(&mut self, amount: U128, lock_seconds: u64) {
+        self.assert_not_paused();
+        assert!(
+            (1..=MAX_STAKE_LOCK_SECONDS).contains(&lock_seconds),
+            "{}",
+            errors::INVALID_LOCK_DURATION
+        );
+        let caller = env::predecessor_account_id();
+        self.internal_stake(&caller, amount.0);
+        let unlock_at = env::block_timestamp() + lock_seconds * 1_000_000_000;
+        let lock = match self.stake_locks.get(&caller) {
+            Some(lock) => StakeLock {
+                amount: U128(lock.amount.0 + amount.0),
+                unlock_at: lock.unlock_at.max(unlock_at),
+                receipt_id: lock.receipt_id,
+            },
+            None => StakeLock {
+                amount,
+                unlock_at,
+                receipt_id: self.mint_stake_receipt(&caller),
+            },
+        };
+        let mut receipt = self
+            .nft_tokens
+            .get(&lock.receipt_id)
+            .expect(errors::TOKEN_NOT_FOUND);
+        receipt.metadata = json!({
+            "kind": "stake_lock",
+            "amount": lock.amount,
+            "unlock_at": lock.unlock_at,
+        })
+        .to_string();
+        self.nft_tokens.insert(&lock.receipt_id, &receipt);
+        self.stake_locks.insert(&caller, &lock);
+        self.emit_with_balances(
+            "stake_locked",
+            json!({
+                "account_id": caller,
+                "amount": amount,
+                "locked_amount": lock.amount,
+                "unlock_at": lock.unlock_at,
+                "receipt_id": lock.receipt_id,
+            }),
+        );
+    }
+
+    /// Returns `account`'s locked stake, if any.
+    pub fn get_stake_lock(&self, account: AccountId) -> Option<StakeLock> {
+        self.stake_locks.get(&account)
+    }
+
+    /// (Admin only) Stake `amounts[i]` of `accounts[i]`'s own liquid balance on
+    /// its behalf, e.g. to import a legacy staking snapshot. Every entry is
+    /// validated (balance, vote locks, stake limits) before anything is staked.
+    /// Accounts may appear only once; at most `MAX_BATCH_QUERY` per call. The
+    /// `stake_batch` event lists each account's post-stake liquid balance in
+    /// `balances`, parallel to `account_ids`.
+    pub fn admin_stake_for(&mut self, accounts: Vec<AccountId>, amounts: Vec<U128>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can stake for other accounts"
+        );
+        self.assert_not_paused();
+        assert_eq!(
+            accounts.len(),
+            amounts.len(),
+            "Accounts and amounts differ in length"
+        );
+        assert!(
+            accounts.len() <= MAX_BATCH_QUERY,
+            "Too many accounts in one batch"
+        );
+        let mut seen = HashSet::new();
+        for (account, amount) in accounts.iter().zip(&amounts) {
+            assert!(seen.insert(account), "Duplicate account in batch");
+            self.assert_can_stake(account, amount.0);
+        }
+        for (account, amount) in accounts.iter().zip(&amounts) {
+            self.internal_stake(account, amount.0);
+        }
+        let balances: Vec<U128> = accounts
+            .iter()
+            .map(|account| U128(self.balance_of_internal(account)))
+            .collect();
+        self.emit(
+            "stake_batch",
+            json!({
+                "admin_id": self.admin,
+                "account_ids": accounts,
+                "amounts": amounts,
+                "balances": balances,
+            }),
+        );
+    }
+
+    /// Unstake tokens: Moves tokens from staked balance back to available balance.
+    /// Unstaking before the minimum stake duration has elapsed since the most recent
+    /// stake forfeits `slash_bps` of the amount to the treasury.
+    pub fn unstake(&mut self, amount: U128) {
+        self.assert_not_paused();
+        let amount: u128 = amount.into();
+        assert!(amount > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+        let caller = env::predecessor_account_id();
+        let current_staked = self.staked.get(&caller).unwrap_or(0);
+        assert!(current_staked >= amount, "{}", errors::INSUFFICIENT_STAKED);
+        if let Some(lock) = self.stake_locks.get(&caller) {
+            if env::block_timestamp() < lock.unlock_at {
+                assert!(
+                    current_staked - amount >= lock.amount.0,
+                    "{}",
+                    errors::STAKE_LOCKED
+                );
+            } else {
+                self.release_stake_lock(&caller, lock);
+            }
+        }
+        self.settle_rewards(&caller);
+        self.set_staked(&caller, current_staked - amount);
+        let started = self.stake_started.get(&caller).unwrap_or(0);
+        let min_duration = self.min_stake_duration_seconds * 1_000_000_000;
+        let slash = if env::block_timestamp() < started + min_duration {
+            // Penalties round down, never taking more than `slash_bps`.
+            apply_bps(amount, self.slash_bps, false)
+        } else {
+            0
+        };
+        let available = self.balance_of_internal(&caller);
+        self.set_balance(&caller, available + amount - slash);
+        if slash > 0 {
+            let treasury = self.treasury.clone();
+            let treasury_balance = self.balance_of_internal(&treasury);
+            self.set_balance(&treasury, treasury_balance + slash);
+        }
+        let mut data =
+            json!({ "account_id": caller, "amount": U128(amount), "slashed": U128(slash) });
+        if slash > 0 {
+            data["treasury_id"] = json!(self.treasury);
+        }
+        self.emit_with_balances("unstake", data);
+    }
+
+    /// (Admin only) Configure the early-unstake penalty and the minimum stake
+    /// duration after which unstaking is penalty-free.
+    pub fn set_slashing(&mut self, slash_bps: u16, min_stake_duration_seconds: u64) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can configure slashing"
+        );
+        assert!(slash_bps <= MAX_SLASH_BPS, "Slash rate too high");
+        self.slash_bps = slash_bps;
+        self.min_stake_duration_seconds = min_stake_duration_seconds;
+        self.emit(
+            "slashing_updated",
+            json!({
+                "slash_bps": slash_bps,
+                "min_stake_duration_seconds": min_stake_duration_seconds,
+            }),
+        );
+    }
+
+    /// (Admin only) Configure the minimum stake amount and the maximum total
+    /// stake per account (0 = unlimited).
+    pub fn set_stake_limits(&mut self, min_stake: U128, max_stake_per_account: U128) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can configure stake limits"
+        );
+        assert!(
+            max_stake_per_account.0 == 0 || min_stake.0 <= max_stake_per_account.0,
+            "Minimum stake exceeds maximum"
+        );
+        self.min_stake = min_stake.0;
+        self.max_stake_per_account = max_stake_per_account.0;
+        self.emit(
+            "stake_limits_updated",
+            json!({ "min_stake": min_stake, "max_stake_per_account": max_stake_per_account }),
+        );
+    }
+
+    /// Returns the minimum amount accepted by a single `stake` call.
+    pub fn get_min_stake(&self) -> U128 {
+        U128(self.min_stake)
+    }
+
+    /// Returns the maximum total stake per account (0 = unlimited).
+    pub fn get_max_stake_per_account(&self) -> U128 {
+        U128(self.max_stake_per_account)
+    }
+
+    /// Returns the early-unstake penalty in basis points.
+    pub fn get_slash_bps(&self) -> u16 {
+        self.slash_bps
+    }
+
+    /// Returns the minimum stake duration (seconds) for penalty-free unstaking.
+    pub fn get_min_stake_duration(&self) -> u64 {
+        self.min_stake_duration_seconds
+    }
+
+    /// Claim staking rewards.
+    /// Rewards accrue continuously at `reward_rate_bps` per year of the staked amount
+    /// since the last claim.
+    /// If `amount` is given, only that much is claimed and the rest stays claimable.
+    pub fn claim_rewards(&mut self, amount: Option<U128>) {
+        self.assert_not_paused();
+        let caller = env::predecessor_account_id();
+        self.internal_claim(&caller, &caller, amount.map(|amount| amount.0));
+    }
+
+    /// Claim all of the caller's staking rewards into `beneficiary`'s balance,
+    /// e.g. for custodians paying out to a designated account.
+    pub fn claim_rewards_to(&mut self, beneficiary: AccountId) {
+        self.assert_not_paused();
+        let caller = env::predecessor_account_id();
+        let reward = self.internal_claim(&caller, &beneficiary, None);
+        self.emit(
+            "claim_rewards_to",
+            json!({ "staker_id": caller, "beneficiary_id": beneficiary, "amount": U128(reward) }),
+        );
+    }
+
+    /// Compound staking rewards: adds claimable rewards directly to the caller's
+    /// staked balance instead of its available balance, so they earn rewards too.
+    /// Subject to the same stake limits as `stake`: only as much as fits under
+    /// `max_stake_per_account` and `max_supply` is restaked, and the rest stays
+    /// claimable.
+    pub fn compound_rewards(&mut self) {
+        self.assert_not_paused();
+        let caller = env::predecessor_account_id();
+        let current_staked = self.staked.get(&caller).unwrap_or(0);
+        let stake_room = if self.max_stake_per_account == 0 {
+            u128::MAX
+        } else {
+            self.max_stake_per_account.saturating_sub(current_staked)
+        };
+        let amount = self
+            .accrued(&caller, env::block_timestamp())
+            .min(stake_room)
+            .min(self.reward_pool.saturating_add(self.mintable_supply()));
+        self.assert_stake_limits(current_staked, amount);
+        let reward = self.take_rewards(&caller, Some(amount));
+        self.set_staked(&caller, current_staked + reward);
+        self.emit(
+            "compound",
+            json!({ "account_id": caller, "amount": U128(reward) }),
+        );
+    }
+
+    /// Stake part of the caller's claimable rewards in one call, without a separate
+    /// claim. Stakes `amount`, or all claimable rewards if less is available; the
+    /// remainder stays claimable. Subject to the same stake limits as `stake`.
+    pub fn stake_from_rewards(&mut self, amount: U128) {
+        self.assert_not_paused();
+        let caller = env::predecessor_account_id();
+        let claimable = self.accrued(&caller, env::block_timestamp());
+        let amount = amount.0.min(claimable);
+        let current_staked = self.staked.get(&caller).unwrap_or(0);
+        self.assert_stake_limits(current_staked, amount);
+        let reward = self.take_rewards(&caller, Some(amount));
+        self.set_staked(&caller, current_staked + reward);
+        self.emit(
+            "stake_from_rewards",
+            json!({ "account_id": caller, "amount": U128(reward) }),
+        );
+    }
+
+    /// (Admin only) Queue setting the annual staking reward rate in basis
+    /// points as `TimelockedAction::SetRewardRate`. Returns the action ID.
+    /// Once executed, the new rate applies to all rewards not yet accrued into a
+    /// claim, including time elapsed since each staker's last claim.
+    pub fn set_reward_rate(&mut self, bps: u16) -> u64 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the reward rate"
+        );
+        assert!(bps <= MAX_REWARD_RATE_BPS, "Reward rate too high");
+        self.internal_queue_action(TimelockedAction::SetRewardRate { bps })
+    }
+
+    /// Returns the annual staking reward rate in basis points.
+    pub fn get_reward_rate(&self) -> u16 {
+        self.reward_rate_bps
+    }
+
+    /// (Admin only) Replace the flat `reward_rate_bps` with a halving emission
+    /// schedule, or restore the flat rate with `None`. Can also be set by a
+    /// passed `ProposalAction::SetEmissionSchedule`. Like `set_reward_rate`, the
+    /// change also applies to rewards accrued since each staker's last claim.
+    pub fn set_emission_schedule(&mut self, schedule: Option<EmissionSchedule>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the emission schedule"
+        );
+        self.internal_set_emission_schedule(schedule);
+    }
+
+    /// Returns the emission schedule, or `None` while the flat rate applies.
+    pub fn get_emission_schedule(&self) -> Option<EmissionSchedule> {
+        self.emission_schedule.clone()
+    }
+
+    /// Returns the effective staking APR in basis points (divide by 100 for a
+    /// percentage). Rewards accrue linearly (simple interest) at
+    /// `reward_rate_bps` per year, or the current rate of the emission schedule,
+    /// and are paid from the reward pool or minted once it is empty, so they
+    /// cannot run dry and the effective APR equals the current rate. If `account` is given
+    /// and holds an NFT, the rate includes the NFT boost:
+    /// `reward_rate_bps * (10_000 + nft_boost_bps) / 10_000`. Compounding with
+    /// `compound_rewards` yields a higher APY.
+    pub fn get_apr(&self, account: Option<AccountId>) -> u16 {
+        match account {
+            Some(account) => self.effective_reward_rate_bps(&account) as u16,
+            None => self.current_reward_rate_bps(env::block_timestamp()) as u16,
+        }
+    }
+
+    /// (Admin only) Set the extra reward, in basis points of the base reward,
+    /// earned by stakers holding at least one NFT. Capped at `MAX_NFT_BOOST_BPS`.
+    pub fn set_nft_boost(&mut self, bps: u16) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the NFT boost"
+        );
+        assert!(bps <= MAX_NFT_BOOST_BPS, "NFT boost too high");
+        self.nft_boost_bps = bps;
+        self.emit("nft_boost_updated", json!({ "bps": bps }));
+    }
+
+    /// Returns the NFT holder reward boost in basis points.
+    pub fn get_nft_boost(&self) -> u16 {
+        self.nft_boost_bps
+    }
+
+    /// Returns total staked, the number of stakers, the current reward emission
+    /// and the activity-funded reward pool with its runway.
+    pub fn get_staking_stats(&self) -> StakingStats {
+        let annual_emission =
+            self.total_staked * self.current_reward_rate_bps(env::block_timestamp()) / 10_000;
+        let runway_seconds = (annual_emission > 0)
+            .then(|| mul_div(self.reward_pool, YEAR_NS / 1_000_000_000, annual_emission) as u64);
+        StakingStats {
+            total_staked: U128(self.total_staked),
+            staker_count: self.staker_count,
+            annual_emission: U128(annual_emission),
+            reward_pool: Some(U128(self.reward_pool)),
+            runway_seconds,
+        }
+    }
+
+    /// (Admin only) Set the share, in basis points, of the transfer burn and
+    /// the withdrawal fee that is routed into the reward pool instead of being
+    /// burned or credited to the treasury (0 disables it).
+    pub fn set_fee_to_pool_bps(&mut self, bps: u16) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the reward pool share"
+        );
+        assert!(bps <= 10_000, "Reward pool share too high");
+        self.fee_to_pool_bps = bps;
+        self.emit("fee_to_pool_bps_updated", json!({ "bps": bps }));
+    }
+
+    /// Returns the share of transfer burns and withdrawal fees funding the
+    /// reward pool, in basis points.
+    pub fn get_fee_to_pool_bps(&self) -> u16 {
+        self.fee_to_pool_bps
+    }
+
+    /// Returns the tokens in the reward pool, which claims draw from before
+    /// minting.
+    pub fn get_reward_pool(&self) -> U128 {
+        U128(self.reward_pool)
+    }
+
+    /// Returns the staking rewards an account can currently claim, including
+    /// any NFT boost.
+    pub fn get_claimable_rewards(&self, account: AccountId) -> U128 {
+        U128(self.accrued(&account, env::block_timestamp()))
+    }
+
+    /// Returns an account's cumulative staked amount, in whole tokens, times
+    /// seconds staked (token-seconds) up to now, rounded down. Informational
+    /// only: rewards are computed from the reward rate and the current stake,
+    /// not from this figure. Counted from the upgrade that introduced it.
+    pub fn get_weighted_stake_seconds(&self, account: AccountId) -> U128 {
+        U128(self.stake_seconds_at(&account, env::block_timestamp()).0)
+    }
+
+    ////////////
+    // Referral System
+    ////////////
+
+    /// Register a referrer for the caller.
+    /// (A caller can register a referrer once; future mints will grant a bonus to that referrer.)
+    /// While `require_referrer_holder` is on, the referrer must hold or stake tokens.
+    pub fn register_referral(&mut self, referrer: AccountId) {
+        let caller = env::predecessor_account_id();
+        assert_ne!(caller, referrer, "{}", errors::CANNOT_REFER_SELF);
+        assert!(
+            self.referrals.get(&caller).is_none(),
+            "{}",
+            errors::REFERRAL_EXISTS
+        );
+        assert!(
+            !self.require_referrer_holder
+                || self.balance_of_internal(&referrer) > 0
+                || self.staked.get(&referrer).unwrap_or(0) > 0,
+            "{}",
+            errors::REFERRER_NOT_HOLDER
+        );
+        self.referrals.insert(&caller, &referrer);
+        let count = self.referral_count.get(&referrer).unwrap_or(0) + 1;
+        self.referral_count.insert(&referrer, &count);
+        self.emit(
+            "referral_registered",
+            json!({ "account_id": caller, "referrer_id": referrer }),
+        );
+    }
+
+    /// (Admin only) Correct or clear the referrer of `account`. Rejects changes
+    /// that would make `account` (indirectly) its own referrer. Regular accounts
+    /// can still only register a referral once, via `register_referral`.
+    pub fn admin_set_referral(&mut self, account: AccountId, referrer: Option<AccountId>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can reassign referrals"
+        );
+        if let Some(referrer) = &referrer {
+            assert_ne!(&account, referrer, "{}", errors::CANNOT_REFER_SELF);
+            // Walk the referral chain starting at `referrer` to reject cycles.
+            let mut next = self.referrals.get(referrer);
+            let mut depth = 0;
+            while let Some(ancestor) = next {
+                assert_ne!(ancestor, account, "Referral cycle detected");
+                depth += 1;
+                assert!(depth <= MAX_REFERRAL_CHAIN_DEPTH, "Referral chain too long");
+                next = self.referrals.get(&ancestor);
+            }
+        }
+        let previous = self.referrals.get(&account);
+        if let Some(previous) = &previous {
+            let count = self
+                .referral_count
+                .get(previous)
+                .unwrap_or(0)
+                .saturating_sub(1);
+            self.referral_count.insert(previous, &count);
+        }
+        match &referrer {
+            Some(referrer) => {
+                self.referrals.insert(&account, referrer);
+                let count = self.referral_count.get(referrer).unwrap_or(0) + 1;
+                self.referral_count.insert(referrer, &count);
+            }
+            None => {
+                self.referrals.remove(&account);
+            }
+        }
+        self.emit(
+            "referral_reassigned",
+            json!({
+                "admin_id": self.admin,
+                "account_id": account,
+                "old_referrer_id": previous,
+                "new_referrer_id": referrer,
+            }),
+        );
+    }
+
+    /// Returns the referrer registered for a given account, if any.
+    pub fn get_referrer(&self, account: AccountId) -> Option<AccountId> {
+        self.referrals.get(&account)
+    }
+
+    /// Returns how many accounts a given account has referred.
+    pub fn get_referral_count(&self, account: AccountId) -> u64 {
+        self.referral_count.get(&account).unwrap_or(0)
+    }
+
+    /// Choose whether the caller's future referral bonuses are credited to its
+    /// stake (`true`) or to its liquid balance (`false`, the default).
+    pub fn set_referral_payout_mode(&mut self, to_stake: bool) {
+        let caller = env::predecessor_account_id();
+        if to_stake {
+            self.referral_to_stake.insert(&caller, &true);
+        } else {
+            self.referral_to_stake.remove(&caller);
+        }
+        self.emit(
+            "referral_payout_mode_updated",
+            json!({ "account_id": caller, "to_stake": to_stake }),
+        );
+    }
+
+    /// Returns whether `account` has its referral bonuses credited to its stake.
+    pub fn get_referral_payout_mode(&self, account: AccountId) -> bool {
+        self.referral_to_stake.get(&account).unwrap_or(false)
+    }
+
+    /// (Admin only) Require referrers passed to `register_referral` to hold a
+    /// liquid or staked balance. Existing referrals are unaffected.
+    pub fn set_require_referrer_holder(&mut self, required: bool) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can configure referrals"
+        );
+        self.require_referrer_holder = required;
+        self.emit(
+            "require_referrer_holder_updated",
+            json!({ "required": required }),
+        );
+    }
+
+    /// Returns whether referrers must hold tokens.
+    pub fn is_referrer_holder_required(&self) -> bool {
+        self.require_referrer_holder
+    }
+
+    /// (Admin only) Cap the cumulative referral bonuses (0 = uncapped). Once the
+    /// cap is reached `mint` still credits the minter but grants no bonus.
+    pub fn set_max_referral_supply(&mut self, amount: U128) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the referral supply cap"
+        );
+        self.max_referral_supply = amount.0;
+        self.emit("max_referral_supply_updated", json!({ "amount": amount }));
+    }
+
+    /// Returns the cap on cumulative referral bonuses (0 = uncapped).
+    pub fn get_max_referral_supply(&self) -> U128 {
+        U128(self.max_referral_supply)
+    }
+
+    /// Returns the cumulative referral bonuses minted.
+    pub fn get_total_referral_minted(&self) -> U128 {
+        U128(self.total_referral_minted)
+    }
+
+    /// Returns the cumulative referral bonuses earned by a given account.
+    pub fn get_referral_earnings(&self, account: AccountId) -> U128 {
+        U128(self.referral_earnings.get(&account).unwrap_or(0))
+    }
+
+    ////////////
+    // Governance & Voting
+    ////////////
+
+    /// (Governor only) Create a new governance proposal.
+    /// The description must be 10-2000 bytes. Voting stays open for the configured
+    /// voting period (7 days by default).
+    /// If `quadratic` is true, votes are weighted by the square root of the voter's
+    /// balance to reduce whale dominance. Defaults to linear (token-weighted) voting.
+    /// If `options` is given, the proposal is multiple choice and is voted on with
+    /// `vote_option`; otherwise it is a binary for/against proposal.
+    /// A binary proposal may carry an `action`, executed when it passes. Such
+    /// proposals can only be finalized once the veto window after the deadline
+    /// has elapsed.
+    #[payable]
+    pub fn propose(
+        &mut self,
+        description: String,
+        quadratic: Option<bool>,
+        options: Option<Vec<String>>,
+        use_staked_weight: Option<bool>,
+        action: Option<ProposalAction>,
+    ) {
+        self.assert_role(Role::Governor);
+        self.internal_propose(description, quadratic, options, use_staked_weight, action);
+    }
+
+    /// Create a proposal as a staker. Open to any account whose staked balance is
+    /// at least `min_stake_to_propose`; disabled while the threshold is 0.
+    pub fn propose_staked(
+        &mut self,
+        description: String,
+        quadratic: Option<bool>,
+        options: Option<Vec<String>>,
+        use_staked_weight: Option<bool>,
+        action: Option<ProposalAction>,
+    ) {
+        assert!(
+            self.min_stake_to_propose > 0,
+            "{}",
+            errors::STAKER_PROPOSALS_DISABLED
+        );
+        let staked = self.staked.get(&env::predecessor_account_id()).unwrap_or(0);
+        assert!(
+            staked >= self.min_stake_to_propose,
+            "{}",
+            errors::STAKE_BELOW_PROPOSE_MINIMUM
+        );
+        self.internal_propose(description, quadratic, options, use_staked_weight, action);
+    }
+
+    /// Returns whether `action` would currently pass the checks `propose` runs
+    /// on proposal actions. Execution can still fail if state changes before
+    /// the proposal passes, e.g. the treasury spends the tokens to be burned.
+    pub fn dry_run_action(&self, action: ProposalAction) -> bool {
+        self.validate_proposal_action(&action).is_ok()
+    }
+
+    /// Vote on an existing binary proposal.
+    /// (Voting power is the caller's current token balance plus the balances of
+    /// accounts that delegated to it, or its integer square root for quadratic proposals.
+    /// Staked-weight proposals use staked balances instead, and require the caller's
+    /// own stake to be at least `min_stake_to_vote`.)
+    ///
+    /// Each account's weight is locked the first time it takes part in a proposal,
+    /// either by voting itself or by being counted through its delegate, and it
+    /// cannot vote on that proposal again. This stops an account from re-voting
+    /// after topping up its balance, but it is not a true creation-time snapshot:
+    /// tokens acquired after the proposal was created still count if they are held
+    /// at the first vote, and tokens moved to another account after voting can be
+    /// voted again by the recipient.
+    pub fn vote(&mut self, proposal_id: u64, support: bool) {
+        self.internal_vote(&env::predecessor_account_id(), proposal_id, support);
+    }
+
+    /// Register the ed25519 public key (32 bytes) whose signatures `delegate_vote`
+    /// and `permit` accept on the caller's behalf. Replaces any previously registered key.
+    pub fn register_signing_key(&mut self, public_key: Vec<u8>) {
+        assert_eq!(public_key.len(), 32, "{}", errors::PUBLIC_KEY_LENGTH);
+        let caller = env::predecessor_account_id();
+        self.signing_keys.insert(&caller, &public_key);
+        self.emit("signing_key_registered", json!({ "account_id": caller }));
+    }
+
+    /// Returns the nonce the next signed vote of `account` must use.
+    pub fn get_vote_nonce(&self, account: AccountId) -> u64 {
+        self.vote_nonces.get(&account).unwrap_or(0)
+    }
+
+    /// Submit a binary vote for `on_behalf_of`, who signed it off-chain with its
+    /// registered signing key, so a relayer can pay the gas. The signed message is
+    /// `"<contract>:vote:<proposal_id>:<support>:<nonce>"`, e.g.
+    /// `"memecoin.near:vote:3:true:0"`, where `nonce` is `get_vote_nonce`; each
+    /// accepted signature bumps the nonce so it cannot be replayed.
+    pub fn delegate_vote(
+        &mut self,
+        proposal_id: u64,
+        support: bool,
+        on_behalf_of: AccountId,
+        signature: Vec<u8>,
+    ) {
+        let nonce = self.vote_nonces.get(&on_behalf_of).unwrap_or(0);
+        let message = format!(
+            "{}:vote:{}:{}:{}",
+            env::current_account_id(),
+            proposal_id,
+            support,
+            nonce
+        );
+        self.assert_signed_by(&on_behalf_of, &message, signature);
+        self.vote_nonces.insert(&on_behalf_of, &(nonce + 1));
+        self.internal_vote(&on_behalf_of, proposal_id, support);
+    }
+
+    /// Vote for one option of a multiple choice proposal.
+    /// Voting weight is computed and locked exactly as in `vote`.
+    pub fn vote_option(&mut self, proposal_id: u64, option_index: usize) {
+        let caller = env::predecessor_account_id();
+        let mut proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect(errors::PROPOSAL_NOT_FOUND);
+        assert!(
+            !proposal.options.is_empty(),
+            "{}",
+            errors::NOT_MULTIPLE_CHOICE
+        );
+        assert!(
+            option_index < proposal.options.len(),
+            "{}",
+            errors::OPTION_OUT_OF_RANGE
+        );
+        let weight = self.record_vote(&mut proposal, &caller, false, Some(option_index as u32));
+        proposal.vote_counts[option_index] += weight;
+        self.proposals.insert(&proposal_id, &proposal);
+        self.emit(
+            "vote",
+            json!({
+                "account_id": caller,
+                "proposal_id": proposal_id,
+                "option_index": option_index,
+                "weight": U128(weight),
+            }),
+        );
+    }
+
+    /// (Admin only) Set the voting period applied to newly created proposals.
+    pub fn set_voting_period(&mut self, seconds: u64) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the voting period"
+        );
+        assert!(
+            seconds > 0 && seconds <= MAX_VOTING_PERIOD_SECONDS,
+            "Voting period must be between 1 second and 90 days"
+        );
+        self.voting_period_seconds = seconds;
+        self.emit("voting_period_updated", json!({ "seconds": seconds }));
+    }
+
+    /// (Admin only) Set how the quorum of new proposals is determined. Existing
+    /// proposals keep the quorum fixed at their creation.
+    pub fn set_quorum_mode(&mut self, mode: QuorumMode) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the quorum"
+        );
+        if let QuorumMode::Percentage { pct } = mode {
+            assert!(
+                (1..=100).contains(&pct),
+                "Quorum percentage must be between 1 and 100"
+            );
+        }
+        self.quorum_mode = mode;
+        self.emit("quorum_mode_updated", json!({ "mode": mode }));
+    }
+
+    /// Returns how the quorum of new proposals is determined.
+    pub fn get_quorum_mode(&self) -> QuorumMode {
+        self.quorum_mode
+    }
+
+    /// (Admin only) Grant votes cast in the first `window_bps` of a new
+    /// proposal's voting period `bonus_bps` extra weight (0 disables it), to
+    /// encourage early turnout. Capped at `MAX_EARLY_VOTE_BONUS_BPS`; existing
+    /// proposals keep the bonus fixed at their creation.
+    pub fn set_early_vote_bonus(&mut self, bonus_bps: u16, window_bps: u16) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the early vote bonus"
+        );
+        assert!(
+            bonus_bps <= MAX_EARLY_VOTE_BONUS_BPS,
+            "Early vote bonus too high"
+        );
+        assert!(
+            window_bps <= 10_000,
+            "Early vote window exceeds the voting period"
+        );
+        self.early_vote_bonus_bps = bonus_bps;
+        self.early_vote_window_bps = window_bps;
+        self.emit(
+            "early_vote_bonus_updated",
+            json!({ "bonus_bps": bonus_bps, "window_bps": window_bps }),
+        );
+    }
+
+    /// Returns the early voter bonus and the share of the voting period it
+    /// applies to, both in basis points.
+    pub fn get_early_vote_bonus(&self) -> (u16, u16) {
+        (self.early_vote_bonus_bps, self.early_vote_window_bps)
+    }
+
+    /// Returns the voting period (seconds) applied to newly created proposals.
+    pub fn get_voting_period(&self) -> u64 {
+        self.voting_period_seconds
+    }
+
+    /// (Admin only) Set the floor on the voting period of new proposals
+    /// (0 disables it). A `voting_period_seconds` below it is clamped up when
+    /// proposals are created. Can also be set by a passed
+    /// `ProposalAction::SetMinVoteDuration`.
+    pub fn set_min_vote_duration(&mut self, seconds: u64) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the minimum vote duration"
+        );
+        self.internal_set_min_vote_duration(seconds);
+    }
+
+    /// (Admin only) Set how long after a proposal's deadline it must wait
+    /// before it can be finalized (0 allows finalizing at the deadline). Can
+    /// also be set by a passed `ProposalAction::SetFinalizationGrace`.
+    pub fn set_finalization_grace(&mut self, seconds: u64) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the finalization grace period"
+        );
+        self.internal_set_finalization_grace(seconds);
+    }
+
+    /// Returns the wait (seconds) between a proposal's deadline and finalization.
+    pub fn get_finalization_grace(&self) -> u64 {
+        self.finalization_grace_seconds
+    }
+
+    /// Returns the floor (seconds) on the voting period of new proposals.
+    pub fn get_min_vote_duration(&self) -> u64 {
+        self.min_vote_duration_seconds
+    }
+
+    /// (Admin only) Set the minimum own stake an account needs to vote on
+    /// staked-weight proposals.
+    pub fn set_min_stake_to_vote(&mut self, amount: U128) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the minimum stake to vote"
+        );
+        self.min_stake_to_vote = amount.0;
+        self.emit("min_stake_to_vote_updated", json!({ "amount": amount }));
+    }
+
+    /// Returns the minimum own stake needed to vote on staked-weight proposals.
+    pub fn get_min_stake_to_vote(&self) -> U128 {
+        U128(self.min_stake_to_vote)
+    }
+
+    /// (Admin only) Set the minimum stake an account needs to create proposals
+    /// through `propose_staked` (0 disables it).
+    pub fn set_min_stake_to_propose(&mut self, amount: U128) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the minimum stake to propose"
+        );
+        self.min_stake_to_propose = amount.0;
+        self.emit("min_stake_to_propose_updated", json!({ "amount": amount }));
+    }
+
+    /// Returns the minimum stake needed to create proposals through `propose_staked`.
+    pub fn get_min_stake_to_propose(&self) -> U128 {
+        U128(self.min_stake_to_propose)
+    }
+
+    /// (Admin only) Set how many proposals may be open (created but neither
+    /// finalized nor cancelled) at once. Lowering it below the current count
+    /// only blocks new proposals.
+    pub fn set_max_active_proposals(&mut self, max: u64) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the active proposal limit"
+        );
+        self.max_active_proposals = max;
+        self.emit("max_active_proposals_updated", json!({ "max": max }));
+    }
+
+    /// Returns the maximum number of open proposals.
+    pub fn get_max_active_proposals(&self) -> u64 {
+        self.max_active_proposals
+    }
+
+    /// Returns the number of proposals neither finalized nor cancelled,
+    /// including those whose voting period ended but await finalization.
+    pub fn get_active_proposal_count(&self) -> u64 {
+        self.active_proposal_count
+    }
+
+    /// (Admin only) Enable or disable vote locking. While enabled, the balance
+    /// backing a vote (the voter's and any counted delegators') cannot be tipped,
+    /// withdrawn or staked until the proposal is finalized or cancelled.
+    /// Locks from concurrent proposals add up. Only votes cast while enabled lock.
+    pub fn set_vote_lock(&mut self, enabled: bool) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can configure vote locking"
+        );
+        self.vote_lock_enabled = enabled;
+        self.emit("vote_lock_updated", json!({ "enabled": enabled }));
+    }
+
+    /// Returns whether vote locking is enabled.
+    pub fn is_vote_lock_enabled(&self) -> bool {
+        self.vote_lock_enabled
+    }
+
+    /// Returns the balance of `account` locked by votes on open proposals.
+    pub fn get_locked_balance(&self, account: AccountId) -> U128 {
+        U128(self.locked_balances.get(&account).unwrap_or(0))
+    }
+
+    /// Returns the per-option tallies of a multiple choice proposal
+    /// (empty for binary proposals).
+    pub fn get_option_tallies(&self, proposal_id: u64) -> Vec<U128> {
+        let proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect(errors::PROPOSAL_NOT_FOUND);
+        proposal.vote_counts.into_iter().map(U128).collect()
+    }
+
+    /// Finalize a proposal (governor only) once its voting deadline and the
+    /// `finalization_grace_seconds` after it have passed.
+    /// For multiple choice proposals, the option with the most weight wins
+    /// (ties go to the lowest index).
+    pub fn finalize_proposal(&mut self, proposal_id: u64) {
+        self.assert_role(Role::Governor);
+        let proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect(errors::PROPOSAL_NOT_FOUND);
+        assert!(!proposal.cancelled, "{}", errors::PROPOSAL_CANCELLED);
+        assert!(!proposal.finalized, "{}", errors::PROPOSAL_FINALIZED);
+        assert!(
+            env::block_timestamp() >= proposal.deadline,
+            "{}",
+            errors::VOTING_NOT_ENDED
+        );
+        assert!(
+            env::block_timestamp() >= self.grace_deadline(&proposal),
+            "{}",
+            errors::FINALIZATION_GRACE
+        );
+        assert!(
+            proposal.action.is_none() || env::block_timestamp() >= veto_deadline(&proposal),
+            "{}",
+            errors::VETO_WINDOW_ACTIVE
+        );
+        self.internal_finalize(proposal);
+    }
+
+    /// (Admin only) Veto a proposal so its action is not executed on
+    /// finalization. Tallies are still recorded, and `reason` is stored in the
+    /// proposal view. Only possible until the veto window after the voting
+    /// deadline ends, and before the proposal is finalized.
+    pub fn veto_proposal(&mut self, proposal_id: u64, reason: String) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can veto proposals"
+        );
+        let mut proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect(errors::PROPOSAL_NOT_FOUND);
+        assert!(!proposal.cancelled, "{}", errors::PROPOSAL_CANCELLED);
+        assert!(!proposal.finalized, "{}", errors::PROPOSAL_FINALIZED);
+        assert!(!proposal.vetoed, "{}", errors::PROPOSAL_VETOED);
+        assert!(
+            env::block_timestamp() < veto_deadline(&proposal),
+            "Veto window has ended"
+        );
+        assert!(
+            (MIN_DESCRIPTION_LEN..=MAX_DESCRIPTION_LEN).contains(&reason.len()),
+            "Reason must be between {} and {} bytes",
+            MIN_DESCRIPTION_LEN,
+            MAX_DESCRIPTION_LEN
+        );
+        proposal.vetoed = true;
+        proposal.veto_reason = Some(reason.clone());
+        self.proposals.insert(&proposal_id, &proposal);
+        self.emit(
+            "proposal_vetoed",
+            json!({ "proposal_id": proposal_id, "admin_id": self.admin, "reason": reason }),
+        );
+    }
+
+    /// Finalize every eligible proposal in `proposal_ids` (governor only).
+    /// Unknown, cancelled, already finalized and still open proposals are
+    /// skipped. At most `MAX_FINALIZE_BATCH` ids per call. Returns the number
+    /// of proposals finalized.
+    pub fn finalize_expired(&mut self, proposal_ids: Vec<u64>) -> u64 {
+        self.assert_role(Role::Governor);
+        assert!(
+            proposal_ids.len() <= MAX_FINALIZE_BATCH,
+            "Too many proposals in one batch"
+        );
+        let now = env::block_timestamp();
+        let mut finalized = 0;
+        for proposal_id in proposal_ids {
+            let proposal = match self.proposals.get(&proposal_id) {
+                Some(proposal) => proposal,
+                None => continue,
+            };
+            if proposal.cancelled || proposal.finalized || now < self.grace_deadline(&proposal) {
+                continue;
+            }
+            if proposal.action.is_some() && now < veto_deadline(&proposal) {
+                continue;
+            }
+            self.internal_finalize(proposal);
+            finalized += 1;
+        }
+        finalized
+    }
+
+    /// Clear stale per-account records so they stop occupying state: vote locks
+    /// from proposals whose voting ended but that were not finalized yet, and
+    /// transfer cooldown entries that have lapsed. Anyone may call it; accounts
+    /// with nothing to sweep are skipped, and for each account whose vote locks
+    /// were released the caller earns `SWEEP_REWARD` from the treasury while its
+    /// balance allows. At most `MAX_BATCH_QUERY` accounts per call. Only the
+    /// `limit` (default and cap `MAX_PAGE_LIMIT`) unfinalized proposals starting
+    /// at `from_index` of the open proposal index are checked for vote locks.
+    /// Returns the number of accounts swept.
+    pub fn sweep_expired_locks(
+        &mut self,
+        accounts: Vec<AccountId>,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> u64 {
+        assert!(
+            accounts.len() <= MAX_BATCH_QUERY,
+            "{}",
+            errors::TOO_MANY_ACCOUNTS
+        );
+        let now = env::block_timestamp();
+        let limit = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+        let expired: Vec<u64> = self
+            .open_proposal_ids
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit as usize)
+            .filter(|id| {
+                self.proposals
+                    .get(id)
+                    .is_some_and(|p| !p.finalized && now >= p.deadline)
+                    && self.vote_locks.get(id).is_some()
+            })
+            .collect();
+        let cooldown_ns = self.transfer_cooldown_seconds * 1_000_000_000;
+        let mut swept = 0;
+        let mut unlocked = 0;
+        for account in &accounts {
+            let mut released = 0;
+            if self.locked_balances.get(account).is_some() {
+                for proposal_id in &expired {
+                    let mut locks = self.vote_locks.get(proposal_id).unwrap_or_default();
+                    let before = locks.len();
+                    locks.retain(|(locked_account, amount)| {
+                        if locked_account == account {
+                            released += amount;
+                        }
+                        locked_account != account
+                    });
+                    if locks.len() != before {
+                        self.vote_locks.insert(proposal_id, &locks);
+                    }
+                }
+            }
+            if released > 0 {
+                let locked = self
+                    .locked_balances
+                    .get(account)
+                    .unwrap_or(0)
+                    .saturating_sub(released);
+                if locked == 0 {
+                    self.locked_balances.remove(account);
+                } else {
+                    self.locked_balances.insert(account, &locked);
+                }
+                unlocked += 1;
+            }
+            let cooldown_lapsed = self
+                .last_transfer
+                .get(account)
+                .is_some_and(|last| now - last >= cooldown_ns);
+            if cooldown_lapsed {
+                self.last_transfer.remove(account);
+            }
+            if released > 0 || cooldown_lapsed {
+                swept += 1;
+            }
+        }
+        let caller = env::predecessor_account_id();
+        let treasury = self.treasury.clone();
+        let treasury_balance = self.balance_of_internal(&treasury);
+        // Only released vote locks are rewarded: cooldown entries are trivial to create.
+        let reward = (unlocked * SWEEP_REWARD).min(treasury_balance);
+        if reward > 0 && caller != treasury {
+            self.set_balance(&treasury, treasury_balance - reward);
+            let caller_balance = self.balance_of_internal(&caller);
+            self.set_balance(&caller, caller_balance + reward);
+            self.emit_ft(
+                "ft_transfer",
+                json!({
+                    "old_owner_id": treasury,
+                    "new_owner_id": caller,
+                    "amount": U128(reward),
+                    "memo": "sweep reward",
+                }),
+            );
+        }
+        self.emit(
+            "locks_swept",
+            json!({ "caller_id": caller, "accounts": swept }),
+        );
+        swept
+    }
+
+    /// Cancel a proposal before its voting deadline. Its proposer or any
+    /// governor may cancel it. A cancelled proposal accepts no further votes
+    /// and cannot be finalized.
+    pub fn cancel_proposal(&mut self, proposal_id: u64) {
+        let mut proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect(errors::PROPOSAL_NOT_FOUND);
+        let caller = env::predecessor_account_id();
+        assert!(
+            caller == proposal.proposer || self.account_has_role(&caller, Role::Governor),
+            "{}",
+            errors::NOT_PROPOSER_OR_GOVERNOR
+        );
+        assert!(!proposal.cancelled, "{}", errors::PROPOSAL_CANCELLED);
+        assert!(
+            env::block_timestamp() < proposal.deadline,
+            "{}",
+            errors::VOTING_ENDED
+        );
+        proposal.cancelled = true;
+        self.proposals.insert(&proposal_id, &proposal);
+        self.proposal_ids.remove(&proposal_id);
+        self.open_proposal_ids.remove(&proposal_id);
+        self.active_proposal_count -= 1;
+        self.release_vote_locks(proposal_id);
+        self.emit("proposal_cancelled", json!({ "proposal_id": proposal_id }));
+    }
+
+    /// Endorse an open proposal to signal interest. Endorsements don't affect
+    /// the tallies; each account can endorse a proposal once.
+    pub fn endorse(&mut self, proposal_id: u64) {
+        let caller = env::predecessor_account_id();
+        let mut proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect(errors::PROPOSAL_NOT_FOUND);
+        assert!(!proposal.cancelled, "{}", errors::PROPOSAL_CANCELLED);
+        assert!(!proposal.finalized, "{}", errors::PROPOSAL_FINALIZED);
+        assert!(
+            env::block_timestamp() < proposal.deadline,
+            "{}",
+            errors::VOTING_ENDED
+        );
+        assert!(
+            self.endorsements.insert(&(proposal_id, caller.clone())),
+            "{}",
+            errors::ALREADY_ENDORSED
+        );
+        proposal.endorsement_count += 1;
+        self.proposals.insert(&proposal_id, &proposal);
+        self.emit(
+            "proposal_endorsed",
+            json!({
+                "proposal_id": proposal_id,
+                "account_id": caller,
+                "endorsement_count": proposal.endorsement_count,
+            }),
+        );
+    }
+
+    /// Returns how `account` voted on a proposal, or `None` if it didn't take
+    /// part. Accounts counted through their delegate show the delegate's
+    /// choice with zero weight.
+    pub fn get_vote(&self, proposal_id: u64, account: AccountId) -> Option<VoteView> {
+        self.votes
+            .get(&(proposal_id, account))
+            .map(|record| VoteView {
+                support: record.support,
+                option: record.option,
+                weight: U128(record.weight),
+            })
+    }
+
+    /// Returns a proposal by ID, if it exists.
+    pub fn get_proposal(&self, proposal_id: u64) -> Option<ProposalView> {
+        self.proposals.get(&proposal_id).map(ProposalView::from)
+    }
+
+    /// Returns proposals that were not cancelled, starting at `from_index` of
+    /// the live proposal index. Creation order is kept until a cancellation
+    /// moves the last entry into the freed slot. `limit` defaults to and is
+    /// capped at `MAX_PAGE_LIMIT`.
+    pub fn get_proposals(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<ProposalView> {
+        let limit = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+        self.proposal_ids
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit as usize)
+            .filter_map(|id| self.proposals.get(&id).map(ProposalView::from))
+            .collect()
+    }
+
+    /// Returns the proposals currently in `status` among proposal IDs
+    /// `from_index..from_index + limit`, with `limit` capped at
+    /// `MAX_PAGE_LIMIT`. A page may hold fewer matches than `limit`, or none;
+    /// advance `from_index` by `limit` until it reaches `next_proposal_id`.
+    pub fn get_proposals_by_status(
+        &self,
+        status: ProposalStatus,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<ProposalView> {
+        let now = env::block_timestamp();
+        let end = self
+            .next_proposal_id
+            .min(from_index.saturating_add(limit.min(MAX_PAGE_LIMIT)));
+        (from_index..end)
+            .filter_map(|id| self.proposals.get(&id))
+            .filter(|proposal| proposal_status(proposal, now) == status)
+            .map(ProposalView::from)
+            .collect()
+    }
+
+    /// Returns the IDs of proposals still open for voting among the `limit`
+    /// (default and cap `MAX_PAGE_LIMIT`) entries starting at `from_index` of
+    /// the open proposal index, which also holds ended but unfinalized ones.
+    pub fn get_active_proposal_ids(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<u64> {
+        let now = env::block_timestamp();
+        let limit = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+        self.open_proposal_ids
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit as usize)
+            .filter(|id| self.proposals.get(id).is_some_and(|p| now < p.deadline))
+            .collect()
+    }
+
+    /// Delegate the caller's voting power to another account.
+    /// Delegation is not transitive: a delegate votes with its own balance plus the
+    /// balances of accounts that delegated directly to it. Re-delegating replaces
+    /// the previous delegate.
+    pub fn delegate(&mut self, to: AccountId) {
+        let caller = env::predecessor_account_id();
+        assert_ne!(caller, to, "{}", errors::CANNOT_DELEGATE_SELF);
+
+        // Walk the delegation chain starting at `to` to reject cycles.
+        let mut next = self.delegations.get(&to);
+        let mut depth = 0;
+        while let Some(account) = next {
+            assert_ne!(account, caller, "{}", errors::DELEGATION_CYCLE);
+            depth += 1;
+            assert!(
+                depth <= MAX_DELEGATION_DEPTH,
+                "{}",
+                errors::DELEGATION_TOO_DEEP
+            );
+            next = self.delegations.get(&account);
+        }
+
+        if self.delegations.get(&caller).is_some() {
+            self.remove_delegation(&caller);
+        }
+        let mut delegators = self.delegators.get(&to).unwrap_or_default();
+        assert!(
+            delegators.len() < MAX_DELEGATORS,
+            "{}",
+            errors::TOO_MANY_DELEGATORS
+        );
+        delegators.push(caller.clone());
+        self.delegators.insert(&to, &delegators);
+        self.delegations.insert(&caller, &to);
+        self.emit(
+            "delegate",
+            json!({ "delegator_id": caller, "delegate_id": to }),
+        );
+    }
+
+    /// Revoke the caller's delegation, restoring its own voting power.
+    pub fn undelegate(&mut self) {
+        let caller = env::predecessor_account_id();
+        let delegate = self.remove_delegation(&caller);
+        self.emit(
+            "undelegate",
+            json!({ "delegator_id": caller, "delegate_id": delegate }),
+        );
+    }
+
+    /// Returns the voting power of an account, accounting for delegation.
+    /// Accounts that have delegated their power have none of their own.
+    pub fn get_voting_power(&self, account: AccountId) -> U128 {
+        if self.delegations.get(&account).is_some() {
+            return U128(0);
+        }
+        U128(self.voting_power(&account))
+    }
+
+    ////////////
+    // Roles & Pausing
+    ////////////
+
+    /// (Admin only) Grant a role to an account.
+    pub fn grant_role(&mut self, account: AccountId, role: Role) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can manage roles"
+        );
+        self.roles.insert(&(account.clone(), role));
+        self.emit(
+            "role_granted",
+            json!({ "account_id": account, "role": role }),
+        );
+    }
+
+    /// (Admin only) Revoke a role from an account.
+    pub fn revoke_role(&mut self, account: AccountId, role: Role) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can manage roles"
+        );
+        self.roles.remove(&(account.clone(), role));
+        self.emit(
+            "role_revoked",
+            json!({ "account_id": account, "role": role }),
+        );
+    }
+
+    /// Returns whether an account holds a role (the admin holds every role).
+    pub fn has_role(&self, account: AccountId, role: Role) -> bool {
+        self.account_has_role(&account, role)
+    }
+
+    /// (Pauser only) Pause token operations immediately, e.g. during an
+    /// incident. Unpausing must go through `TimelockedAction::SetPaused`.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.assert_role(Role::Pauser);
+        assert!(paused, "Unpausing requires a timelocked action");
+        self.internal_set_paused(paused);
+    }
+
+    /// Returns whether token operations are paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// (Admin only) Enable or disable transfers without pausing minting,
+    /// staking or withdrawals. The admin and treasury can always transfer.
+    pub fn set_transfers_enabled(&mut self, enabled: bool) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can toggle transfers"
+        );
+        self.transfers_enabled = enabled;
+        self.emit("transfers_enabled_updated", json!({ "enabled": enabled }));
+    }
+
+    /// Returns whether transfers are enabled.
+    pub fn are_transfers_enabled(&self) -> bool {
+        self.transfers_enabled
+    }
+
+    ////////////
+    // Timelock
+    ////////////
+
+    /// (Admin only) Queue a sensitive action. It can be executed once the timelock
+    /// delay has passed, giving holders advance notice. Returns the action ID.
+    pub fn queue_action(&mut self, action: TimelockedAction) -> u64 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can queue actions"
+        );
+        self.internal_queue_action(action)
+    }
+
+    /// (Admin only) Execute a queued action whose timelock delay has passed.
+    pub fn execute_action(&mut self, id: u64) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can execute actions"
+        );
+        let queued = self.queued_actions.get(&id).expect("Action not found");
+        assert!(
+            env::block_timestamp() >= queued.execute_after,
+            "Timelock has not expired"
+        );
+        self.queued_actions.remove(&id);
+        match queued.action {
+            TimelockedAction::AdminBurn { account, amount } => {
+                self.internal_admin_burn(&account, amount.0)
+            }
+            TimelockedAction::SetPaused { paused } => self.internal_set_paused(paused),
+            TimelockedAction::SetRewardRate { bps } => self.internal_set_reward_rate(bps),
+            TimelockedAction::SetTreasury { treasury } => self.internal_set_treasury(treasury),
+            TimelockedAction::SetTimelockDelay { seconds } => {
+                self.timelock_delay_seconds = seconds;
+                self.emit("timelock_delay_updated", json!({ "seconds": seconds }));
+            }
+            TimelockedAction::SetDailyWithdrawLimit { limit } => {
+                self.internal_set_daily_withdraw_limit(limit.0)
+            }
+            TimelockedAction::MergeAccounts { from, to } => {
+                self.internal_merge_accounts(&from, &to)
+            }
+            TimelockedAction::ReleaseReserve { amount } => self.internal_release_reserve(amount.0),
+            TimelockedAction::RecoverNear { amount } => self.internal_recover_near(amount.0),
+        }
+        self.emit("action_executed", json!({ "action_id": id }));
+    }
+
+    /// (Admin only) Cancel a queued action before it executes.
+    pub fn cancel_action(&mut self, id: u64) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can cancel actions"
+        );
+        self.queued_actions.remove(&id).expect("Action not found");
+        self.emit("action_cancelled", json!({ "action_id": id }));
+    }
+
+    /// Returns a queued action by ID, if it is still pending.
+    pub fn get_queued_action(&self, id: u64) -> Option<QueuedAction> {
+        self.queued_actions.get(&id)
+    }
+
+    /// Returns the timelock delay in seconds.
+    pub fn get_timelock_delay(&self) -> u64 {
+        self.timelock_delay_seconds
+    }
+
+    ////////////
+    // Treasury
+    ////////////
+
+    /// (Admin only) Queue changing the treasury account that receives fees and
+    /// penalties as `TimelockedAction::SetTreasury`. Returns the action ID.
+    pub fn set_treasury(&mut self, treasury: AccountId) -> u64 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the treasury"
+        );
+        self.internal_queue_action(TimelockedAction::SetTreasury { treasury })
+    }
+
+    /// Returns the treasury account.
+    pub fn get_treasury(&self) -> AccountId {
+        self.treasury.clone()
+    }
+
+    /// (Admin only) Move `amount` of the admin's liquid balance into the admin
+    /// reserve, which is excluded from the circulating supply.
+    pub fn set_admin_reserve(&mut self, amount: U128) {
+        let admin = env::predecessor_account_id();
+        assert_eq!(admin, self.admin, "Only admin can manage the reserve");
+        assert!(amount.0 > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+        let balance = self.balance_of_internal(&admin);
+        assert!(balance >= amount.0, "{}", errors::INSUFFICIENT_BALANCE);
+        self.assert_unlocked(&admin, balance, amount.0);
+        self.set_balance(&admin, balance - amount.0);
+        self.admin_reserve += amount.0;
+        self.emit_with_balances(
+            "admin_reserve_added",
+            json!({
+                "admin_id": admin,
+                "account_id": admin,
+                "amount": amount,
+                "reserve": U128(self.admin_reserve),
+            }),
+        );
+    }
+
+    /// (Admin only) Queue moving `amount` of the admin reserve back to the
+    /// admin's liquid balance as `TimelockedAction::ReleaseReserve`, so holders
+    /// see the float grow before it does. Returns the action ID.
+    pub fn release_reserve(&mut self, amount: U128) -> u64 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can manage the reserve"
+        );
+        assert!(
+            amount.0 <= self.admin_reserve,
+            "Amount exceeds the admin reserve"
+        );
+        self.internal_queue_action(TimelockedAction::ReleaseReserve { amount })
+    }
+
+    /// Returns the tokens held in the admin reserve.
+    pub fn get_admin_reserve(&self) -> U128 {
+        U128(self.admin_reserve)
+    }
+
+    /// (Admin only) Queue sending `amount` of NEAR that was transferred to the
+    /// contract outside of `mint` to the treasury, as
+    /// `TimelockedAction::RecoverNear`. Only the surplus reported by
+    /// `get_unaccounted_near` can be recovered, checked again on execution.
+    /// Returns the action ID.
+    pub fn recover_unaccounted_near(&mut self, amount: U128) -> u64 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can recover NEAR"
+        );
+        assert!(amount.0 > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+        assert!(
+            amount.0 <= self.unaccounted_near(),
+            "Amount exceeds the unaccounted NEAR"
+        );
+        self.internal_queue_action(TimelockedAction::RecoverNear { amount })
+    }
+
+    /// Returns the contract's NEAR not needed to back tokens: its balance minus
+    /// storage costs, `total_supply` (every token can be withdrawn 1:1),
+    /// undelivered `mint` refunds and prepaid NFT storage credit. Staking
+    /// rewards not yet claimed are not backed, as they are minted on claim.
+    pub fn get_unaccounted_near(&self) -> U128 {
+        U128(self.unaccounted_near())
+    }
+
+    ////////////
+    // NFTs
+    ////////////
+
+    /// Mint an NFT with the provided metadata, owned by `receiver_id` (the caller
+    /// if `None`), e.g. to gift it or hand out an achievement reward.
+    /// The caller is recorded as the creator and receives `royalty_bps` of future sales.
+    /// The attached deposit must cover the mint price plus the storage used by the
+    /// NFT; the price goes to the treasury and any excess is refunded to the caller.
+    #[payable]
+    pub fn nft_mint(
+        &mut self,
+        metadata: String,
+        royalty_bps: Option<u16>,
+        receiver_id: Option<AccountId>,
+    ) {
+        self.with_lock(|this| {
+            let deposit: NearToken = env::attached_deposit();
+            let deposit_amount = deposit.as_yoctonear();
+            let caller = env::predecessor_account_id();
+            let receiver = receiver_id.unwrap_or_else(|| caller.clone());
+            let initial_storage = env::storage_usage();
+            this.internal_mint_nft(&receiver, &caller, metadata, royalty_bps);
+            let storage_cost = env::storage_byte_cost().as_yoctonear()
+                * (env::storage_usage() - initial_storage) as u128;
+            let required = storage_cost + this.nft_mint_price;
+            assert!(
+                deposit_amount >= required,
+                "Attached deposit too low for NFT minting: {} yoctoNEAR short",
+                required - deposit_amount
+            );
+            if this.nft_mint_price > 0 {
+                Promise::new(this.treasury.clone())
+                    .transfer(NearToken::from_yoctonear(this.nft_mint_price));
+            }
+            let refund = deposit_amount - required;
+            if refund > 0 {
+                Promise::new(caller).transfer(NearToken::from_yoctonear(refund));
+            }
+        })
+    }
+
+    /// (Admin only) Set the maximum NFT metadata length in bytes.
+    pub fn set_nft_max_metadata_len(&mut self, max_len: u32) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the metadata limit"
+        );
+        self.nft_max_metadata_len = max_len;
+        self.emit("nft_metadata_limit_updated", json!({ "max_len": max_len }));
+    }
+
+    /// Returns the maximum NFT metadata length in bytes.
+    pub fn get_nft_max_metadata_len(&self) -> u32 {
+        self.nft_max_metadata_len
+    }
+
+    /// (Admin only) Set the NFT mint price and the supply cap (0 = unlimited).
+    /// The cap cannot be set below the number of NFTs already minted.
+    pub fn set_nft_sale_config(&mut self, mint_price: U128, max_supply: u64) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can configure NFT sales"
+        );
+        assert!(
+            max_supply == 0 || max_supply >= self.sold_nft_count(),
+            "Supply cap below minted count"
+        );
+        self.nft_mint_price = mint_price.0;
+        self.nft_max_supply = max_supply;
+        self.emit(
+            "nft_sale_config_updated",
+            json!({ "mint_price": mint_price, "max_supply": max_supply }),
+        );
+    }
+
+    /// Returns the NFT mint price (excluding storage).
+    pub fn get_nft_mint_price(&self) -> U128 {
+        U128(self.nft_mint_price)
+    }
+
+    /// Returns how many more NFTs can be minted, or `None` if supply is uncapped.
+    pub fn get_nft_remaining_supply(&self) -> Option<u64> {
+        (self.nft_max_supply > 0).then(|| self.nft_max_supply.saturating_sub(self.sold_nft_count()))
+    }
+
+    /// Returns the number of NFTs sold so far, not counting stake receipts.
+    pub fn get_nft_total_minted(&self) -> u64 {
+        self.sold_nft_count()
+    }
+
+    /// Returns the number of soulbound stake receipts currently held.
+    pub fn get_stake_receipt_count(&self) -> u64 {
+        self.stake_receipts_minted - self.nft_burned
+    }
+
+    /// Transfer an NFT owned by the caller to `receiver_id`.
+    /// Requires exactly 1 yoctoNEAR attached, as in NEP-171.
+    #[payable]
+    pub fn nft_transfer(&mut self, receiver_id: AccountId, token_id: u64, memo: Option<String>) {
+        assert_eq!(
+            env::attached_deposit().as_yoctonear(),
+            1,
+            "{}",
+            errors::REQUIRES_ONE_YOCTO
+        );
+        let mut nft = self
+            .nft_tokens
+            .get(&token_id)
+            .expect(errors::TOKEN_NOT_FOUND);
+        assert!(
+            !self.soulbound_tokens.contains(&token_id),
+            "{}",
+            errors::TOKEN_SOULBOUND
+        );
+        let sender = env::predecessor_account_id();
+        assert_eq!(nft.owner, sender, "{}", errors::NOT_TOKEN_OWNER);
+        assert_ne!(sender, receiver_id, "{}", errors::CANNOT_TRANSFER_SELF);
+        self.remove_token_from_owner(&sender, token_id);
+        self.add_token_to_owner(&receiver_id, token_id);
+        nft.owner = receiver_id.clone();
+        self.nft_tokens.insert(&token_id, &nft);
+        self.emit(
+            "nft_transfer",
+            json!({
+                "old_owner_id": sender,
+                "new_owner_id": receiver_id,
+                "token_id": token_id,
+                "memo": memo,
+            }),
+        );
+    }
+
+    /// Returns an NFT by token ID, if it exists.
+    pub fn nft_token(&self, token_id: u64) -> Option<TokenView> {
+        self.nft_tokens
+            .get(&token_id)
+            .map(|nft| self.token_view(token_id, nft))
+    }
+
+    /// Returns the number of sold NFTs in existence. Stake receipts are
+    /// reported by `get_stake_receipt_count` instead.
+    pub fn nft_total_supply(&self) -> U128 {
+        U128(self.sold_nft_count() as u128)
+    }
+
+    /// Returns NFTs in token ID order, starting at `from_index` (default 0).
+    /// `limit` defaults to and is capped at `MAX_PAGE_LIMIT`.
+    pub fn nft_tokens(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<TokenView> {
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+        let end = self.next_token_id.min(from_index.saturating_add(limit));
+        (from_index..end)
+            .filter_map(|token_id| {
+                self.nft_tokens
+                    .get(&token_id)
+                    .map(|nft| self.token_view(token_id, nft))
+            })
+            .collect()
+    }
+
+    /// Returns the number of NFTs owned by `account_id`.
+    pub fn nft_supply_for_owner(&self, account_id: AccountId) -> U128 {
+        let count = self
+            .tokens_per_owner
+            .get(&account_id)
+            .map_or(0, |tokens| tokens.len());
+        U128(count as u128)
+    }
+
+    /// Returns the NFTs owned by `account_id`, paginated like `nft_tokens`.
+    pub fn nft_tokens_for_owner(
+        &self,
+        account_id: AccountId,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<TokenView> {
+        let tokens = match self.tokens_per_owner.get(&account_id) {
+            Some(tokens) => tokens,
+            None => return Vec::new(),
+        };
+        let limit = limit.unwrap_or(MAX_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+        tokens
+            .iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit as usize)
+            .filter_map(|token_id| {
+                self.nft_tokens
+                    .get(&token_id)
+                    .map(|nft| self.token_view(token_id, nft))
+            })
+            .collect()
+    }
+
+    /// NEP-199: computes how a sale of `balance` for the given token is split
+    /// between the creator (royalty) and the current owner (remainder).
+    pub fn nft_payout(&self, token_id: u64, balance: U128, max_len_payout: u32) -> Payout {
+        let nft = self
+            .nft_tokens
+            .get(&token_id)
+            .expect(errors::TOKEN_NOT_FOUND);
+        // Royalties round down; the owner receives the exact remainder.
+        let royalty = apply_bps(balance.0, nft.royalty_bps, false);
+        let mut payout = HashMap::new();
+        if royalty > 0 && nft.creator != nft.owner {
+            payout.insert(nft.creator, U128(royalty));
+            payout.insert(nft.owner, U128(balance.0 - royalty));
+        } else {
+            payout.insert(nft.owner, balance);
+        }
+        assert!(
+            payout.len() <= max_len_payout as usize,
+            "Payout exceeds max_len_payout"
+        );
+        Payout { payout }
+    }
+
+    ////////////
+    // Accepted Tokens
+    ////////////
+
+    /// NEP-141 receiver hook, called by an accepted token contract after a
+    /// `ft_transfer_call` to this contract. `msg` is a JSON `FtTransferIntent`;
+    /// `buy_nft` mints an NFT to `sender_id` at the token's price and returns
+    /// the excess to be refunded. The NFT's storage is paid from the NEAR
+    /// `sender_id` prepaid with `deposit_nft_storage`. Any failure refunds the
+    /// whole amount.
+    pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> U128 {
+        self.assert_not_paused();
+        let token = env::predecessor_account_id();
+        let price = self
+            .accepted_tokens
+            .get(&token)
+            .expect(errors::FT_NOT_ACCEPTED);
+        let intent: FtTransferIntent =
+            near_sdk::serde_json::from_str(&msg).expect(errors::INVALID_TRANSFER_MSG);
+        match intent {
+            FtTransferIntent::BuyNft {
+                metadata,
+                royalty_bps,
+            } => {
+                assert!(amount.0 >= price, "{}", errors::FT_PAYMENT_TOO_LOW);
+                let initial_storage = env::storage_usage();
+                let token_id =
+                    self.internal_mint_nft(&sender_id, &sender_id, metadata, royalty_bps);
+                let proceeds = self.ft_proceeds.get(&token).unwrap_or(0) + price;
+                self.ft_proceeds.insert(&token, &proceeds);
+                let storage_cost = env::storage_byte_cost().as_yoctonear()
+                    * (env::storage_usage() - initial_storage) as u128;
+                let credit = self.nft_storage_credits.get(&sender_id).unwrap_or(0);
+                assert!(
+                    credit >= storage_cost,
+                    "{}",
+                    errors::NFT_STORAGE_NOT_COVERED
+                );
+                self.nft_storage_credits
+                    .insert(&sender_id, &(credit - storage_cost));
+                self.total_nft_storage_credits -= storage_cost;
+                self.emit(
+                    "nft_bought_with_ft",
+                    json!({
+                        "owner_id": sender_id,
+                        "token_id": token_id,
+                        "ft_contract_id": token,
+                        "price": U128(price),
+                    }),
+                );
+                U128(amount.0 - price)
+            }
+        }
+    }
+
+    /// (Admin only) Accept NEP-141 transfers from `token_contract`, selling NFTs
+    /// for `nft_price` (non-zero) of its units, or stop accepting it with `None`.
+    /// Proceeds already received stay withdrawable.
+    pub fn set_accepted_token(&mut self, token_contract: AccountId, nft_price: Option<U128>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set accepted tokens"
+        );
+        match nft_price {
+            Some(price) => {
+                assert!(price.0 > 0, "NFT price must be positive");
+                self.accepted_tokens.insert(&token_contract, &price.0);
+            }
+            None => {
+                self.accepted_tokens.remove(&token_contract);
+            }
+        }
+        self.emit(
+            "accepted_token_updated",
+            json!({ "token_contract_id": token_contract, "nft_price": nft_price }),
+        );
+    }
+
+    /// (Admin only) Send `amount` of the `token_contract` units received for
+    /// NFTs to the treasury, which must be registered with that token. If the
+    /// transfer fails, the amount stays withdrawable.
+    pub fn withdraw_ft_proceeds(&mut self, token_contract: AccountId, amount: U128) -> Promise {
+        self.with_lock(|this| {
+            assert_eq!(
+                env::predecessor_account_id(),
+                this.admin,
+                "Only admin can withdraw token proceeds"
+            );
+            assert!(amount.0 > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+            let proceeds = this.ft_proceeds.get(&token_contract).unwrap_or(0);
+            assert!(amount.0 <= proceeds, "Amount exceeds the token proceeds");
+            this.ft_proceeds
+                .insert(&token_contract, &(proceeds - amount.0));
+            this.emit(
+                "ft_proceeds_withdrawn",
+                json!({
+                    "token_contract_id": token_contract,
+                    "receiver_id": this.treasury,
+                    "amount": amount,
+                }),
+            );
+            ext_fungible_token::ext(token_contract.clone())
+                .with_attached_deposit(NearToken::from_yoctonear(1))
+                .with_static_gas(FT_TRANSFER_GAS)
+                .ft_transfer(
+                    this.treasury.clone(),
+                    amount,
+                    Some("nft proceeds".to_string()),
+                )
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(FT_TRANSFER_CALLBACK_GAS)
+                        .on_ft_proceeds_withdrawn(token_contract, amount),
+                )
+        })
+    }
+
+    /// Restores the proceeds of a failed `withdraw_ft_proceeds`.
+    #[private]
+    pub fn on_ft_proceeds_withdrawn(
+        &mut self,
+        token_contract: AccountId,
+        amount: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) {
+        self.with_lock(|this| {
+            if result.is_ok() {
+                return;
+            }
+            let proceeds = this.ft_proceeds.get(&token_contract).unwrap_or(0) + amount.0;
+            this.ft_proceeds.insert(&token_contract, &proceeds);
+            this.emit(
+                "ft_proceeds_restored",
+                json!({ "token_contract_id": token_contract, "amount": amount }),
+            );
+        })
+    }
+
+    /// Returns the units of `token_contract` received for NFTs and not yet
+    /// withdrawn.
+    pub fn get_ft_proceeds(&self, token_contract: AccountId) -> U128 {
+        U128(self.ft_proceeds.get(&token_contract).unwrap_or(0))
+    }
+
+    /// Prepay NEAR for the storage of NFTs `account_id` (default: the caller)
+    /// buys with accepted tokens. Unused credit can be taken back with
+    /// `withdraw_nft_storage`.
+    #[payable]
+    pub fn deposit_nft_storage(&mut self, account_id: Option<AccountId>) -> U128 {
+        let amount = env::attached_deposit().as_yoctonear();
+        assert!(amount > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+        let account = account_id.unwrap_or_else(env::predecessor_account_id);
+        let credit = self.nft_storage_credits.get(&account).unwrap_or(0) + amount;
+        self.nft_storage_credits.insert(&account, &credit);
+        self.total_nft_storage_credits += amount;
+        U128(credit)
+    }
+
+    /// Send the caller's unused NFT storage credit back to it.
+    pub fn withdraw_nft_storage(&mut self) -> Promise {
+        self.with_lock(|this| {
+            let caller = env::predecessor_account_id();
+            let amount = this
+                .nft_storage_credits
+                .remove(&caller)
+                .filter(|amount| *amount > 0)
+                .expect(errors::NO_PENDING_REFUND);
+            this.total_nft_storage_credits -= amount;
+            Promise::new(caller.clone())
+                .transfer(NearToken::from_yoctonear(amount))
+                .then(
+                    Self::ext(env::current_account_id())
+                        .with_static_gas(NFT_STORAGE_REFUND_CALLBACK_GAS)
+                        .on_nft_storage_refund_resolved(caller, U128(amount)),
+                )
+        })
+    }
+
+    /// Credits a failed `withdraw_nft_storage` back to `account`.
+    #[private]
+    pub fn on_nft_storage_refund_resolved(
+        &mut self,
+        account: AccountId,
+        amount: U128,
+        #[callback_result] result: Result<(), PromiseError>,
+    ) {
+        self.with_lock(|this| {
+            if result.is_ok() {
+                return;
+            }
+            let credit = this.nft_storage_credits.get(&account).unwrap_or(0) + amount.0;
+            this.nft_storage_credits.insert(&account, &credit);
+            this.total_nft_storage_credits += amount.0;
+        })
+    }
+
+    /// Returns `account`'s unused NFT storage credit.
+    pub fn get_nft_storage_credit(&self, account: AccountId) -> U128 {
+        U128(self.nft_storage_credits.get(&account).unwrap_or(0))
+    }
+
+    /// Returns every accepted token contract with its NFT price.
+    pub fn get_accepted_tokens(&self) -> Vec<(AccountId, U128)> {
+        self.accepted_tokens
+            .iter()
+            .map(|(token, price)| (token, U128(price)))
+            .collect()
+    }
+
+    ////////////
+    // State Export & Migration
+    ////////////
+
+    /// Exports liquid balances in insertion order, starting at `from_index`.
+    /// `limit` is capped at `MAX_PAGE_LIMIT`. Intended for audits and bridge
+    /// migrations; page through against an archival node to get a consistent
+    /// snapshot at a fixed block height.
+    pub fn export_balances(&self, from_index: u64, limit: u64) -> Vec<(AccountId, U128)> {
+        self.balances
+            .iter()
+            .skip(from_index as usize)
+            .take(limit.min(MAX_PAGE_LIMIT) as usize)
+            .map(|(account, balance)| (account, U128(balance)))
+            .collect()
+    }
+
+    /// Exports staked amounts, paginated like `export_balances`.
+    pub fn export_staked(&self, from_index: u64, limit: u64) -> Vec<(AccountId, U128)> {
+        self.staked
+            .iter()
+            .skip(from_index as usize)
+            .take(limit.min(MAX_PAGE_LIMIT) as usize)
+            .map(|(account, amount)| (account, U128(amount)))
+            .collect()
+    }
+
+    /// Converts the original release's state layout after a code upgrade.
+    /// `balances` and `staked` start empty under new prefixes; the old entries
+    /// are moved over with `migrate_accounts`, and the stored proposals are
+    /// converted with `migrate_proposals`. Every setting the original release
+    /// lacked starts at its `new` default, except that the contract starts
+    /// paused: queue `TimelockedAction::SetPaused` to unpause once every account
+    /// and proposal has been migrated.
+    ///
+    /// State already in the current layout only has its version bumped, which
+    /// must be strictly older than this code's, so the same release cannot be
+    /// migrated twice.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let state = env::storage_read(b"STATE").expect("No state to migrate");
+        if let Ok(current) = Self::try_from_slice(&state) {
+            assert!(
+                parse_version(&current.version) < parse_version(CONTRACT_VERSION),
+                "State is already at version {}",
+                current.version
+            );
+            env::log_str(&format!(
+                "Migrated from {} to {}",
+                current.version, CONTRACT_VERSION
+            ));
+            return Self {
+                version: CONTRACT_VERSION.to_string(),
+                ..current
+            };
+        }
+        let old = LegacyContract::try_from_slice(&state).expect("No state to migrate");
+        Self {
+            balances: UnorderedMap::new(b"B".to_vec()),
+            total_supply: old.total_supply,
+            admin: old.admin.clone(),
+            referrals: old.referrals,
+            staked: UnorderedMap::new(b"K".to_vec()),
+            proposals: LookupMap::new(b"p".to_vec()),
+            next_proposal_id: old.next_proposal_id,
+            tip_totals: old.tip_totals,
+            top_tipper: old.top_tipper,
+            delegations: LookupMap::new(b"d".to_vec()),
+            delegators: LookupMap::new(b"D".to_vec()),
+            votes: LookupMap::new(b"v".to_vec()),
+            referral_count: LookupMap::new(b"c".to_vec()),
+            referral_earnings: LookupMap::new(b"e".to_vec()),
+            last_claim: LookupMap::new(b"l".to_vec()),
+            unclaimed_rewards: LookupMap::new(b"u".to_vec()),
+            treasury: old.admin,
+            slash_bps: 0,
+            min_stake_duration_seconds: 0,
+            stake_started: LookupMap::new(b"S".to_vec()),
+            min_stake: 0,
+            max_stake_per_account: 0,
+            reward_rate_bps: DEFAULT_REWARD_RATE_BPS,
+            holder_index: TreeMap::new(b"h".to_vec()),
+            holder_count: 0,
+            transfer_cooldown_seconds: 0,
+            last_transfer: LookupMap::new(b"L".to_vec()),
+            nft_tokens: LookupMap::new(b"n".to_vec()),
+            next_token_id: 0,
+            nft_max_metadata_len: DEFAULT_NFT_MAX_METADATA_LEN,
+            roles: LookupSet::new(b"R".to_vec()),
+            paused: true,
+            timelock_delay_seconds: DEFAULT_TIMELOCK_DELAY_SECONDS,
+            queued_actions: LookupMap::new(b"q".to_vec()),
+            next_action_id: 0,
+            event_seq: 0,
+            voting_period_seconds: DEFAULT_VOTING_PERIOD_SECONDS,
+            withdraw_fee_bps: 0,
+            min_withdraw: 0,
+            min_mint_deposit: DEFAULT_MIN_MINT_DEPOSIT,
+            vote_lock_enabled: false,
+            locked_balances: LookupMap::new(b"k".to_vec()),
+            vote_locks: LookupMap::new(b"x".to_vec()),
+            referral_to_stake: LookupMap::new(b"o".to_vec()),
+            total_burned: 0,
+            burned_by: LookupMap::new(b"y".to_vec()),
+            allowances: LookupMap::new(b"a".to_vec()),
+            burn_bps: 0,
+            tippers: Vector::new(b"T".to_vec()),
+            tipper_index: LookupMap::new(b"F".to_vec()),
+            nft_mint_price: 0,
+            nft_max_supply: 0,
+            redeem_rate_bps: 0,
+            min_stake_to_vote: 0,
+            oracle: None,
+            signing_keys: LookupMap::new(b"g".to_vec()),
+            vote_nonces: LookupMap::new(b"N".to_vec()),
+            dust_threshold: 0,
+            total_staked: 0,
+            staker_count: 0,
+            permit_nonces: LookupMap::new(b"P".to_vec()),
+            max_referral_supply: 0,
+            total_referral_minted: 0,
+            transfers_enabled: true,
+            quorum_mode: QuorumMode::None,
+            tokens_per_owner: LookupMap::new(b"O".to_vec()),
+            nft_boost_bps: 0,
+            daily_withdraw_limit: 0,
+            withdrawn_today: (0, 0),
+            merged_into: LookupMap::new(b"M".to_vec()),
+            min_stake_to_propose: 0,
+            stake_locks: LookupMap::new(b"j".to_vec()),
+            soulbound_tokens: LookupSet::new(b"f".to_vec()),
+            stake_receipts_minted: 0,
+            nft_burned: 0,
+            max_supply: 0,
+            pending_mint_refunds: LookupMap::new(b"w".to_vec()),
+            admin_reserve: 0,
+            version: CONTRACT_VERSION.to_string(),
+            proposal_ids: UnorderedSet::new(b"i".to_vec()),
+            emission_schedule: None,
+            total_pending_mint_refunds: 0,
+            active_proposal_count: 0,
+            max_active_proposals: DEFAULT_MAX_ACTIVE_PROPOSALS,
+            require_referrer_holder: false,
+            endorsements: LookupSet::new(b"m".to_vec()),
+            reward_pool: 0,
+            fee_to_pool_bps: 0,
+            early_vote_bonus_bps: 0,
+            early_vote_window_bps: 0,
+            accepted_tokens: UnorderedMap::new(b"z".to_vec()),
+            min_vote_duration_seconds: DEFAULT_MIN_VOTE_DURATION_SECONDS,
+            metadata_icon: None,
+            locked: false,
+            total_deposit_minted: 0,
+            total_reward_minted: 0,
+            total_minter_minted: 0,
+            stake_seconds: LookupMap::new(b"A".to_vec()),
+            finalization_grace_seconds: 0,
+            legacy_proposals: (0, old.next_proposal_id),
+            ft_proceeds: LookupMap::new(b"E".to_vec()),
+            nft_storage_credits: LookupMap::new(b"C".to_vec()),
+            total_nft_storage_credits: 0,
+            open_proposal_ids: UnorderedSet::new(b"G".to_vec()),
+        }
+    }
+
+    /// Moves the balances and stakes of `accounts` from the legacy `LookupMap`
+    /// storage into the iterable collections, adding to anything the account
+    /// already holds there. Accounts already migrated, or without legacy
+    /// entries, are skipped. Migrated stakes earn rewards from now on. Admin
+    /// only; at most `MAX_BATCH_QUERY` accounts per call.
+    pub fn migrate_accounts(&mut self, accounts: Vec<AccountId>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can migrate accounts"
+        );
+        assert!(
+            accounts.len() <= MAX_BATCH_QUERY,
+            "Too many accounts in one batch"
+        );
+        let mut legacy_balances: LookupMap<AccountId, u128> = LookupMap::new(b"b".to_vec());
+        let mut legacy_staked: LookupMap<AccountId, u128> = LookupMap::new(b"s".to_vec());
+        for account in accounts {
+            if let Some(balance) = legacy_balances.remove(&account) {
+                let current = self.balance_of_internal(&account);
+                self.set_balance(&account, current + balance);
+                self.emit_with_balances(
+                    "balance_migrated",
+                    json!({ "account_id": account, "amount": U128(balance) }),
+                );
+            }
+            if let Some(amount) = legacy_staked.remove(&account) {
+                self.settle_rewards(&account);
+                let current = self.staked.get(&account).unwrap_or(0);
+                self.set_staked(&account, current + amount);
+                self.stake_started.insert(&account, &env::block_timestamp());
+            }
+        }
+    }
+
+    /// Converts up to `limit` (at most `MAX_BATCH_QUERY`) proposals stored in
+    /// the original layout, in ID order, and returns how many are left. Legacy
+    /// proposals have no recorded proposer and are attributed to the admin;
+    /// finalized ones passed if more weight was cast for than against. Admin
+    /// only.
+    pub fn migrate_proposals(&mut self, limit: u64) -> u64 {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can migrate proposals"
+        );
+        let legacy: LookupMap<u64, LegacyProposal> = LookupMap::new(b"p".to_vec());
+        let (start, end) = self.legacy_proposals;
+        let stop = end.min(start + limit.min(MAX_BATCH_QUERY as u64));
+        for id in start..stop {
+            let Some(old) = legacy.get(&id) else {
+                continue;
+            };
+            self.proposal_ids.insert(&id);
+            if !old.finalized {
+                self.open_proposal_ids.insert(&id);
+                self.active_proposal_count += 1;
+            }
+            let proposal = Proposal {
+                id: old.id,
+                description: old.description,
+                votes_for: old.votes_for,
+                votes_against: old.votes_against,
+                deadline: old.deadline,
+                finalized: old.finalized,
+                quadratic: false,
+                cancelled: false,
+                options: Vec::new(),
+                vote_counts: Vec::new(),
+                use_staked_weight: false,
+                passed: old.finalized && old.votes_for > old.votes_against,
+                winning_option: None,
+                supply_snapshot: 0,
+                quorum: 0,
+                proposer: self.admin.clone(),
+                action: None,
+                vetoed: false,
+                veto_reason: None,
+                endorsement_count: 0,
+                early_vote_bonus_bps: 0,
+                early_vote_ends: 0,
+                voter_count: 0,
+                raw_turnout: old.votes_for + old.votes_against,
+            };
+            self.proposals.insert(&id, &proposal);
+        }
+        self.legacy_proposals = (stop, end);
+        end - stop
+    }
+
+    ////////////
+    // Contract Info
+    ////////////
+
+    /// Returns the current supply figures together with the `event_seq` of the
+    /// last event they include.
+    pub fn get_state_checkpoint(&self) -> StateCheckpoint {
+        StateCheckpoint {
+            total_supply: U128(self.total_supply),
+            total_burned: U128(self.total_burned),
+            event_seq: self.event_seq,
+            holder_count: self.holder_count,
+        }
+    }
+
+    /// (Admin only) Replace the token icon, a `data:image/` URI of at most
+    /// `MAX_ICON_LEN` bytes, or remove it with `None`. Can also be set by a
+    /// passed `ProposalAction::SetMetadataIcon`.
+    pub fn set_metadata_icon(&mut self, icon: Option<String>) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.admin,
+            "Only admin can set the token icon"
+        );
+        self.internal_set_metadata_icon(icon);
+    }
+
+    /// Returns the token icon data URI, if set.
+    pub fn get_metadata_icon(&self) -> Option<String> {
+        self.metadata_icon.clone()
+    }
+
+    /// Returns the headline supply, staking and governance figures in one call.
+    /// Every field is read from a maintained counter, so this is O(1).
+    pub fn get_contract_stats(&self) -> ContractStats {
+        ContractStats {
+            total_supply: U128(self.total_supply),
+            circulating_supply: U128(self.circulating_supply()),
+            total_staked: U128(self.total_staked),
+            total_burned: U128(self.total_burned),
+            holder_count: self.holder_count,
+            staker_count: self.staker_count,
+            reward_pool: U128(self.reward_pool),
+            treasury_balance: U128(self.balance_of_internal(&self.treasury)),
+            next_proposal_id: self.next_proposal_id,
+            active_proposal_count: self.active_proposal_count,
+            paused: self.paused,
+        }
+    }
+
+    /// Returns cumulative tokens minted by source and burned, with an annualized
+    /// inflation estimate from the current staking emission.
+    pub fn get_inflation_rate(&self) -> InflationStats {
+        let annual_emission =
+            self.total_staked * self.current_reward_rate_bps(env::block_timestamp()) / 10_000;
+        let annual_inflation_bps = if self.total_supply == 0 {
+            0
+        } else {
+            mul_div(annual_emission, 10_000, self.total_supply) as u64
+        };
+        InflationStats {
+            total_deposit_minted: U128(self.total_deposit_minted),
+            total_referral_minted: U128(self.total_referral_minted),
+            total_reward_minted: U128(self.total_reward_minted),
+            total_minter_minted: U128(self.total_minter_minted),
+            total_burned: U128(self.total_burned),
+            annual_emission: U128(annual_emission),
+            annual_inflation_bps,
+        }
+    }
+
+    /// Returns the version of the code that initialized or last migrated the state.
+    pub fn get_version(&self) -> String {
+        self.version.clone()
+    }
+
+    /// Returns every tunable parameter of the contract in a single call.
+    pub fn get_contract_config(&self) -> ContractConfig {
+        ContractConfig {
+            version: self.version.clone(),
+            admin: self.admin.clone(),
+            treasury: self.treasury.clone(),
+            total_supply: U128(self.total_supply),
+            next_proposal_id: self.next_proposal_id,
+            paused: self.paused,
+            reward_rate_bps: self.reward_rate_bps,
+            emission_schedule: self.emission_schedule.clone(),
+            slash_bps: self.slash_bps,
+            min_stake_duration_seconds: self.min_stake_duration_seconds,
+            min_stake: U128(self.min_stake),
+            max_stake_per_account: U128(self.max_stake_per_account),
+            transfer_cooldown_seconds: self.transfer_cooldown_seconds,
+            withdraw_fee_bps: self.withdraw_fee_bps,
+            min_withdraw: U128(self.min_withdraw),
+            voting_period_seconds: self.voting_period_seconds,
+            min_vote_duration_seconds: self.min_vote_duration_seconds,
+            finalization_grace_seconds: self.finalization_grace_seconds,
+            timelock_delay_seconds: self.timelock_delay_seconds,
+            nft_max_metadata_len: self.nft_max_metadata_len,
+            min_mint_deposit: U128(self.min_mint_deposit),
+            max_supply: U128(self.max_supply),
+            vote_lock_enabled: self.vote_lock_enabled,
+            burn_bps: self.burn_bps,
+            fee_to_pool_bps: self.fee_to_pool_bps,
+            nft_mint_price: U128(self.nft_mint_price),
+            nft_max_supply: self.nft_max_supply,
+            redeem_rate_bps: self.redeem_rate_bps,
+            min_stake_to_vote: U128(self.min_stake_to_vote),
+            min_stake_to_propose: U128(self.min_stake_to_propose),
+            max_active_proposals: self.max_active_proposals,
+            early_vote_bonus_bps: self.early_vote_bonus_bps,
+            early_vote_window_bps: self.early_vote_window_bps,
+            oracle: self.oracle.clone(),
+            dust_threshold: U128(self.dust_threshold),
+            max_referral_supply: U128(self.max_referral_supply),
+            require_referrer_holder: self.require_referrer_holder,
+            transfers_enabled: self.transfers_enabled,
+            quorum_mode: self.quorum_mode,
+            nft_boost_bps: self.nft_boost_bps,
+            daily_withdraw_limit: U128(self.daily_withdraw_limit),
+        }
+    }
+
+    /// Returns the sequence number of the most recently emitted event (0 if none).
+    /// Every event carries its `event_seq` in `data`, so indexers can detect gaps.
+    pub fn get_event_seq(&self) -> u64 {
+        self.event_seq
+    }
+
+    ////////////
+    // Leaderboard
+    ////////////
+
+    /// Returns the account of the top tipper (i.e. the account that has tipped the most cumulatively).
+    pub fn get_top_tipper(&self) -> Option<AccountId> {
+        self.top_tipper.clone()
+    }
+
+    /// Returns cumulative tip totals in order of each account's first tip,
+    /// starting at `from_index`. `limit` is capped at `MAX_PAGE_LIMIT`.
+    pub fn get_tip_totals(&self, from_index: u64, limit: u64) -> Vec<(AccountId, U128)> {
+        let end = self
+            .tippers
+            .len()
+            .min(from_index.saturating_add(limit.min(MAX_PAGE_LIMIT)));
+        (from_index..end)
+            .filter_map(|index| self.tippers.get(index))
+            .map(|account| {
+                let total = self.tip_totals.get(&account).unwrap_or(0);
+                (account, U128(total))
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Rewards claimable by `account` at timestamp `now`: the carried-over
+    /// unclaimed amount plus rewards accrued on its stake since `last_claim`.
+    /// The single source of truth for views and claims alike: it only reads
+    /// storage, each key at most once, and skips the boost lookup for accounts
+    /// with nothing staked.
+    fn accrued(&self, account: &AccountId, now: u64) -> u128 {
+        let unclaimed = self.unclaimed_rewards.get(account).unwrap_or(0);
+        let last_claim = match self.last_claim.get(account) {
+            Some(last_claim) => last_claim,
+            None => return unclaimed,
+        };
+        let staked_amount = self.staked.get(account).unwrap_or(0);
+        if staked_amount == 0 {
+            return unclaimed;
+        }
+        let boost_bps = self.nft_boost_of(account);
+        let earned: u128 = self
+            .reward_rate_segments(last_claim, now)
+            .into_iter()
+            .map(|(rate_bps, elapsed)| {
+                let annual_reward = staked_amount * apply_boost(rate_bps, boost_bps) / 10_000;
+                mul_div(annual_reward, elapsed, YEAR_NS)
+            })
+            .sum();
+        unclaimed + earned
+    }
+
+    /// The current annual reward rate of `account` in basis points, including
+    /// its NFT boost.
+    fn effective_reward_rate_bps(&self, account: &AccountId) -> u128 {
+        let rate_bps = self.current_reward_rate_bps(env::block_timestamp());
+        apply_boost(rate_bps, self.nft_boost_of(account))
+    }
+
+    /// `nft_boost_bps` if `account` holds an NFT, otherwise 0. The boost
+    /// applies to the whole period since the last claim.
+    fn nft_boost_of(&self, account: &AccountId) -> u128 {
+        if self.nft_boost_bps == 0 {
+            return 0;
+        }
+        // Stake receipts are soulbound and do not count as holding an NFT.
+        let held = self
+            .tokens_per_owner
+            .get(account)
+            .map_or(0, |tokens| tokens.len());
+        let receipts = self.stake_locks.get(account).is_some() as u64;
+        if held <= receipts {
+            return 0;
+        }
+        self.nft_boost_bps as u128
+    }
+
+    /// The unboosted annual reward rate at timestamp `now`.
+    fn current_reward_rate_bps(&self, now: u64) -> u128 {
+        match &self.emission_schedule {
+            None => self.reward_rate_bps as u128,
+            Some(schedule) if now < schedule.start => 0,
+            Some(schedule) => {
+                let interval = schedule.halving_interval_seconds * 1_000_000_000;
+                let halvings = (now - schedule.start) / interval;
+                (schedule.initial_rate_bps as u128)
+                    .checked_shr(halvings as u32)
+                    .unwrap_or(0)
+            }
+        }
+    }
+
+    /// Splits `[from, to)` into (rate_bps, duration_ns) pieces of constant
+    /// reward rate: a single piece at the flat rate, or one piece per halving
+    /// period of the emission schedule. Time before the schedule starts or
+    /// after the rate reaches 0 is left out.
+    fn reward_rate_segments(&self, from: u64, to: u64) -> Vec<(u128, u128)> {
+        let schedule = match &self.emission_schedule {
+            None => {
+                return vec![(
+                    self.reward_rate_bps as u128,
+                    to.saturating_sub(from) as u128,
+                )]
+            }
+            Some(schedule) => schedule,
+        };
+        let interval = schedule.halving_interval_seconds as u128 * 1_000_000_000;
+        let start = schedule.start as u128;
+        let to = to as u128;
+        let mut from = (from as u128).max(start);
+        let mut segments = Vec::new();
+        while from < to {
+            let period = (from - start) / interval;
+            let rate = (schedule.initial_rate_bps as u128)
+                .checked_shr(period as u32)
+                .unwrap_or(0);
+            if rate == 0 {
+                break;
+            }
+            let end = (start + (period + 1) * interval).min(to);
+            segments.push((rate, end - from));
+            from = end;
+        }
+        segments
+    }
+
+    /// Keeps `top_tipper` pointing at the account with the highest cumulative tips.
+    /// A tip only changes the sender's total and totals never decrease, so comparing
+    /// the sender's new total against the current leader's total preserves the
+    /// maximum. Ties keep the existing leader.
+    fn update_top_tipper(&mut self, sender: &AccountId, sender_total: u128) {
+        let leader_total = match &self.top_tipper {
+            Some(leader) if leader == sender => return,
+            Some(leader) => self.tip_totals.get(leader).unwrap_or(0),
+            None => 0,
+        };
+        if sender_total > leader_total {
+            self.top_tipper = Some(sender.clone());
+        }
+    }
+
+    /// Logs a NEP-297 event of this contract's own standard.
+    fn emit(&mut self, event: &str, data: Value) {
+        self.emit_event(EVENT_STANDARD, EVENT_STANDARD_VERSION, event, vec![data]);
+    }
+
+    /// Logs a memecoin standard event that changes liquid balances, with the
+    /// post-event balances attached like `emit_ft_batch`. Only events that move
+    /// tokens use this, so NFT and bookkeeping events carry no balance fields.
+    fn emit_with_balances(&mut self, event: &str, data: Value) {
+        let mut data = vec![data];
+        self.attach_post_balances(&mut data);
+        self.emit_event(EVENT_STANDARD, EVENT_STANDARD_VERSION, event, data);
+    }
+
+    /// Logs a NEP-141 (`ft_mint`, `ft_burn`, `ft_transfer`) event.
+    fn emit_ft(&mut self, event: &str, data: Value) {
+        self.emit_ft_batch(event, vec![data]);
+    }
+
+    /// Logs a NEP-141 event carrying several entries in its `data` array.
+    /// Each entry gets the liquid balance, after the whole call's changes so
+    /// far, of every account it names: `balance` for `owner_id`, and
+    /// `old_owner_balance` / `new_owner_balance` for transfers.
+    fn emit_ft_batch(&mut self, event: &str, mut data: Vec<Value>) {
+        self.attach_post_balances(&mut data);
+        self.emit_event(FT_EVENT_STANDARD, FT_EVENT_STANDARD_VERSION, event, data);
+    }
+
+    /// Adds the current liquid balance of every account an event entry names
+    /// under the matching key of `POST_BALANCE_KEYS`. Every event logged
+    /// through here names the accounts whose liquid balance it changed under
+    /// one of those keys.
+    fn attach_post_balances(&self, data: &mut [Value]) {
+        for entry in data.iter_mut() {
+            for (account_key, balance_key) in POST_BALANCE_KEYS {
+                let account = entry
+                    .get(account_key)
+                    .and_then(Value::as_str)
+                    .and_then(|account| account.parse::<AccountId>().ok());
+                if let Some(account) = account {
+                    entry[balance_key] = json!(U128(self.balance_of_internal(&account)));
+                }
+            }
+        }
+    }
+
+    /// Assigns the next `event_seq` to every entry of `data` and logs them as
+    /// one NEP-297 event. Every event goes through here so the sequence has no gaps.
+    fn emit_event(&mut self, standard: &str, version: &str, event: &str, mut data: Vec<Value>) {
+        self.event_seq += 1;
+        for entry in data.iter_mut() {
+            entry["event_seq"] = json!(self.event_seq);
+        }
+        let event = json!({
+            "standard": standard,
+            "version": version,
+            "event": event,
+            "data": data,
+        });
+        env::log_str(&format!("EVENT_JSON:{}", event));
+    }
+
+    fn internal_burn_treasury(&mut self, amount: u128) {
+        assert!(amount > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+        let treasury = self.treasury.clone();
+        let balance = self.balance_of_internal(&treasury);
+        assert!(
+            balance >= amount,
+            "{}",
+            errors::INSUFFICIENT_BALANCE_TO_BURN
+        );
+        self.assert_unlocked(&treasury, balance, amount);
+        self.set_balance(&treasury, balance - amount);
+        self.total_supply -= amount;
+        self.record_burn(&treasury, amount);
+        self.emit_ft(
+            "ft_burn",
+            json!({ "owner_id": treasury, "amount": U128(amount), "memo": "treasury burn" }),
+        );
+    }
+
+    fn internal_admin_burn(&mut self, account: &AccountId, amount: u128) {
+        let current_balance = self.balance_of_internal(account);
+        assert!(
+            current_balance >= amount,
+            "{}",
+            errors::INSUFFICIENT_BALANCE_TO_BURN
+        );
+        self.set_balance(account, current_balance - amount);
+        self.total_supply -= amount;
+        self.record_burn(account, amount);
+        self.emit_ft(
+            "ft_burn",
+            json!({ "owner_id": account, "amount": U128(amount), "memo": "admin burn" }),
+        );
+        self.emit(
+            "admin_burn",
+            json!({ "admin_id": self.admin, "account_id": account, "amount": U128(amount) }),
+        );
+    }
+
+    /// Rejects `signature` unless it is `account`'s registered signing key's
+    /// ed25519 signature over `message`.
+    fn assert_signed_by(&self, account: &AccountId, message: &str, signature: Vec<u8>) {
+        let public_key = self
+            .signing_keys
+            .get(account)
+            .expect(errors::NO_SIGNING_KEY);
+        let signature: [u8; 64] = signature.try_into().expect(errors::SIGNATURE_LENGTH);
+        let public_key: [u8; 32] = public_key.try_into().expect("Invalid signing key");
+        assert!(
+            env::ed25519_verify(&signature, message.as_bytes(), &public_key),
+            "{}",
+            errors::INVALID_SIGNATURE
+        );
+    }
+
+    /// Builds the JSON view of an NFT.
+    fn token_view(&self, token_id: u64, nft: Nft) -> TokenView {
+        TokenView {
+            token_id,
+            owner_id: nft.owner,
+            creator_id: nft.creator,
+            metadata: nft.metadata,
+            royalty_bps: nft.royalty_bps,
+            soulbound: self.soulbound_tokens.contains(&token_id),
+        }
+    }
+
+    /// Mints an empty soulbound receipt to `owner` and returns its token ID.
+    /// Receipts do not count toward `nft_max_supply`.
+    fn mint_stake_receipt(&mut self, owner: &AccountId) -> u64 {
+        let token_id = self.next_token_id;
+        let receipt = Nft {
+            owner: owner.clone(),
+            creator: env::current_account_id(),
+            metadata: String::new(),
+            royalty_bps: 0,
+        };
+        self.nft_tokens.insert(&token_id, &receipt);
+        self.add_token_to_owner(owner, token_id);
+        self.soulbound_tokens.insert(&token_id);
+        self.next_token_id += 1;
+        self.stake_receipts_minted += 1;
+        self.emit(
+            "nft_mint",
+            json!({ "owner_id": owner, "token_id": token_id, "soulbound": true }),
+        );
+        token_id
+    }
+
+    /// Drops `owner`'s expired stake lock and burns its receipt.
+    fn release_stake_lock(&mut self, owner: &AccountId, lock: StakeLock) {
+        self.stake_locks.remove(owner);
+        self.nft_tokens.remove(&lock.receipt_id);
+        self.soulbound_tokens.remove(&lock.receipt_id);
+        self.remove_token_from_owner(owner, lock.receipt_id);
+        self.nft_burned += 1;
+        self.emit(
+            "nft_burn",
+            json!({ "owner_id": owner, "token_id": lock.receipt_id }),
+        );
+    }
+
+    /// Number of NFTs minted through `nft_mint`, which `nft_max_supply` caps.
+    fn sold_nft_count(&self) -> u64 {
+        self.next_token_id - self.stake_receipts_minted
+    }
+
+    /// Adds `token_id` to `owner`'s token index.
+    fn add_token_to_owner(&mut self, owner: &AccountId, token_id: u64) {
+        let mut tokens = self.tokens_per_owner.get(owner).unwrap_or_else(|| {
+            let mut prefix = b"U".to_vec();
+            prefix.extend(env::sha256(owner.as_bytes()));
+            UnorderedSet::new(prefix)
+        });
+        tokens.insert(&token_id);
+        self.tokens_per_owner.insert(owner, &tokens);
+    }
+
+    /// Removes `token_id` from `owner`'s token index.
+    fn remove_token_from_owner(&mut self, owner: &AccountId, token_id: u64) {
+        let mut tokens = self
+            .tokens_per_owner
+            .get(owner)
+            .expect("Owner has no tokens");
+        tokens.remove(&token_id);
+        if tokens.is_empty() {
+            self.tokens_per_owner.remove(owner);
+        } else {
+            self.tokens_per_owner.insert(owner, &tokens);
+        }
+    }
+
+    /// Stores the allowance of `spender` over `owner`'s balance, dropping zero entries.
+    fn set_allowance(&mut self, owner: &AccountId, spender: &AccountId, amount: u128) {
+        let key = (owner.clone(), spender.clone());
+        if amount == 0 {
+            self.allowances.remove(&key);
+        } else {
+            self.allowances.insert(&key, &amount);
+        }
+    }
+
+    /// Adds a burn of `amount` from `account` to the burn statistics.
+    fn record_burn(&mut self, account: &AccountId, amount: u128) {
+        self.total_burned += amount;
+        let burned = self.burned_by.get(account).unwrap_or(0) + amount;
+        self.burned_by.insert(account, &burned);
+    }
+
+    fn execute_proposal_action(&mut self, proposal_id: u64, action: ProposalAction) {
+        self.emit(
+            "proposal_action_executed",
+            json!({ "proposal_id": proposal_id, "action": action }),
+        );
+        match action {
+            ProposalAction::SetRewardRate { bps } => self.internal_set_reward_rate(bps),
+            ProposalAction::SetBurnBps { bps } => self.internal_set_burn_bps(bps),
+            ProposalAction::SetTreasury { treasury } => self.internal_set_treasury(treasury),
+            ProposalAction::SetPaused { paused } => self.internal_set_paused(paused),
+            ProposalAction::SetEmissionSchedule { schedule } => {
+                self.internal_set_emission_schedule(schedule)
+            }
+            ProposalAction::BurnTreasury { amount } => self.internal_burn_treasury(amount.0),
+            ProposalAction::SetMinVoteDuration { seconds } => {
+                self.internal_set_min_vote_duration(seconds)
+            }
+            ProposalAction::SetMetadataIcon { icon } => self.internal_set_metadata_icon(icon),
+            ProposalAction::SetFinalizationGrace { seconds } => {
+                self.internal_set_finalization_grace(seconds)
+            }
+        }
+    }
+
+    /// Checks `action` against the bounds its setter enforces at execution,
+    /// so unexecutable proposals are rejected before anyone votes on them.
+    fn validate_proposal_action(&self, action: &ProposalAction) -> Result<(), String> {
+        let ok = |valid: bool, reason: &str| {
+            if valid {
+                Ok(())
+            } else {
+                Err(reason.to_string())
+            }
+        };
+        match action {
+            ProposalAction::SetRewardRate { bps } => {
+                ok(*bps <= MAX_REWARD_RATE_BPS, "Reward rate too high")
+            }
+            ProposalAction::SetBurnBps { bps } => {
+                ok(*bps <= MAX_BURN_BPS, "Transfer burn too high")
+            }
+            ProposalAction::SetTreasury { .. } | ProposalAction::SetPaused { .. } => Ok(()),
+            ProposalAction::SetEmissionSchedule { schedule: None } => Ok(()),
+            ProposalAction::SetEmissionSchedule {
+                schedule: Some(schedule),
+            } => {
+                ok(
+                    schedule.initial_rate_bps <= MAX_REWARD_RATE_BPS,
+                    "Reward rate too high",
+                )?;
+                ok(
+                    schedule.halving_interval_seconds > 0,
+                    "Halving interval must be positive",
+                )
+            }
+            ProposalAction::BurnTreasury { amount } => {
+                ok(amount.0 > 0, errors::AMOUNT_NOT_POSITIVE)?;
+                ok(
+                    amount.0 <= self.balance_of_internal(&self.treasury),
+                    errors::INSUFFICIENT_BALANCE_TO_BURN,
+                )
+            }
+            ProposalAction::SetMinVoteDuration { seconds } => ok(
+                *seconds <= MAX_VOTING_PERIOD_SECONDS,
+                "Minimum vote duration cannot exceed 90 days",
+            ),
+            ProposalAction::SetFinalizationGrace { seconds } => ok(
+                *seconds <= MAX_VOTING_PERIOD_SECONDS,
+                "Finalization grace cannot exceed 90 days",
+            ),
+            ProposalAction::SetMetadataIcon { icon: None } => Ok(()),
+            ProposalAction::SetMetadataIcon { icon: Some(icon) } => {
+                ok(
+                    icon.starts_with("data:image/"),
+                    "Icon must be a data:image/ URI",
+                )?;
+                ok(icon.len() <= MAX_ICON_LEN, "Icon too large")
+            }
+        }
+    }
+
+    fn internal_set_metadata_icon(&mut self, icon: Option<String>) {
+        if let Some(icon) = &icon {
+            assert!(
+                icon.starts_with("data:image/"),
+                "Icon must be a data:image/ URI"
+            );
+            assert!(icon.len() <= MAX_ICON_LEN, "Icon too large");
+        }
+        self.emit("metadata_icon_updated", json!({ "icon": icon }));
+        self.metadata_icon = icon;
+    }
+
+    fn internal_set_finalization_grace(&mut self, seconds: u64) {
+        assert!(
+            seconds <= MAX_VOTING_PERIOD_SECONDS,
+            "Finalization grace cannot exceed 90 days"
+        );
+        self.finalization_grace_seconds = seconds;
+        self.emit("finalization_grace_updated", json!({ "seconds": seconds }));
+    }
+
+    fn internal_set_min_vote_duration(&mut self, seconds: u64) {
+        assert!(
+            seconds <= MAX_VOTING_PERIOD_SECONDS,
+            "Minimum vote duration cannot exceed 90 days"
+        );
+        self.min_vote_duration_seconds = seconds;
+        self.emit("min_vote_duration_updated", json!({ "seconds": seconds }));
+    }
+
+    fn internal_set_emission_schedule(&mut self, schedule: Option<EmissionSchedule>) {
+        if let Some(schedule) = &schedule {
+            assert!(
+                schedule.initial_rate_bps <= MAX_REWARD_RATE_BPS,
+                "Reward rate too high"
+            );
+            assert!(
+                schedule.halving_interval_seconds > 0,
+                "Halving interval must be positive"
+            );
+        }
+        self.emit("emission_schedule_updated", json!({ "schedule": schedule }));
+        self.emission_schedule = schedule;
+    }
+
+    fn internal_set_burn_bps(&mut self, bps: u16) {
+        assert!(bps <= MAX_BURN_BPS, "Transfer burn too high");
+        self.burn_bps = bps;
+        self.emit("burn_bps_updated", json!({ "bps": bps }));
+    }
+
+    fn internal_set_reward_rate(&mut self, bps: u16) {
+        assert!(bps <= MAX_REWARD_RATE_BPS, "Reward rate too high");
+        self.emit(
+            "reward_rate_updated",
+            json!({ "old_bps": self.reward_rate_bps, "new_bps": bps }),
+        );
+        self.reward_rate_bps = bps;
+    }
+
+    fn internal_set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.emit(
+            "paused_updated",
+            json!({ "account_id": env::predecessor_account_id(), "paused": paused }),
+        );
+    }
+
+    fn internal_merge_accounts(&mut self, from: &AccountId, to: &AccountId) {
+        assert_ne!(from, to, "Cannot merge an account into itself");
+        assert!(
+            self.stake_locks.get(from).is_none(),
+            "{}",
+            errors::STAKE_LOCKED
+        );
+        assert!(
+            self.merged_into.get(from).is_none(),
+            "Account already merged"
+        );
+        assert!(
+            self.locked_balances.get(from).is_none(),
+            "{}",
+            errors::BALANCE_LOCKED
+        );
+
+        let balance = self.balance_of_internal(from);
+        let to_balance = self.balance_of_internal(to);
+        self.set_balance(from, 0);
+        self.balances.remove(from);
+        self.set_balance(to, to_balance + balance);
+
+        // Settle both accounts' rewards and stake-seconds before their stakes
+        // are combined.
+        let now = env::block_timestamp();
+        let from_seconds = self.stake_seconds_at(from, now);
+        let to_seconds = self.stake_seconds_at(to, now);
+        self.stake_seconds.remove(from);
+        if from_seconds != (0, 0) || to_seconds != (0, 0) {
+            let total = add_stake_seconds(
+                (to_seconds.0 + from_seconds.0, to_seconds.1 + from_seconds.1),
+                0,
+                0,
+            );
+            self.stake_seconds.insert(to, &total);
+        }
+        let rewards = self.accrued(from, now) + self.accrued(to, now);
+        self.unclaimed_rewards.remove(from);
+        self.last_claim.remove(from);
+        if rewards > 0 {
+            self.unclaimed_rewards.insert(to, &rewards);
+        }
+        let staked = self.staked.get(from).unwrap_or(0);
+        let to_staked = self.staked.get(to).unwrap_or(0);
+        self.set_staked(from, 0);
+        self.set_staked(to, to_staked + staked);
+        if staked + to_staked > 0 {
+            self.last_claim.insert(to, &now);
+        }
+        if let Some(started) = self.stake_started.remove(from) {
+            if self.stake_started.get(to).is_none() {
+                self.stake_started.insert(to, &started);
+            }
+        }
+
+        let tips = self.tip_totals.remove(from).unwrap_or(0);
+        if let Some(index) = self.tipper_index.remove(from) {
+            if self.tip_totals.get(to).is_none() {
+                // `to` takes over `from`'s place in the first-tip order.
+                self.tippers.replace(index, to);
+                self.tipper_index.insert(to, &index);
+            } else {
+                self.tippers.swap_remove(index);
+                if let Some(moved) = self.tippers.get(index) {
+                    self.tipper_index.insert(&moved, &index);
+                }
+            }
+        }
+        if tips > 0 {
+            let previous_total = self.tip_totals.get(to);
+            let total = previous_total.unwrap_or(0) + tips;
+            self.tip_totals.insert(to, &total);
+            if self.top_tipper.as_ref() == Some(from) {
+                self.top_tipper = Some(to.clone());
+            }
+            self.update_top_tipper(to, total);
+        }
+
+        // `to` cannot stay referred by the account merged into it.
+        if self.referrals.get(to).as_ref() == Some(from) {
+            self.referrals.remove(to);
+            let count = self.referral_count.get(from).unwrap_or(0).saturating_sub(1);
+            self.referral_count.insert(from, &count);
+        }
+        if let Some(referrer) = self.referrals.remove(from) {
+            if self.referrals.get(to).is_none() && referrer != *to {
+                self.referrals.insert(to, &referrer);
+            } else {
+                let count = self
+                    .referral_count
+                    .get(&referrer)
+                    .unwrap_or(0)
+                    .saturating_sub(1);
+                self.referral_count.insert(&referrer, &count);
+            }
+        }
+        let referral_count = self.referral_count.remove(from).unwrap_or(0);
+        if referral_count > 0 {
+            let count = self.referral_count.get(to).unwrap_or(0) + referral_count;
+            self.referral_count.insert(to, &count);
+        }
+        let earnings = self.referral_earnings.remove(from).unwrap_or(0);
+        if earnings > 0 {
+            let total = self.referral_earnings.get(to).unwrap_or(0) + earnings;
+            self.referral_earnings.insert(to, &total);
+        }
+        self.merged_into.insert(from, to);
+
+        self.emit_with_balances(
+            "accounts_merged",
+            json!({
+                "admin_id": self.admin,
+                "from_account_id": from,
+                "to_account_id": to,
+                "balance": U128(balance),
+                "staked": U128(staked),
+                "tip_total": U128(tips),
+                "referral_count": referral_count,
+                "referral_earnings": U128(earnings),
+            }),
+        );
+    }
+
+    fn internal_set_daily_withdraw_limit(&mut self, limit: u128) {
+        self.daily_withdraw_limit = limit;
+        self.emit(
+            "daily_withdraw_limit_updated",
+            json!({ "limit": U128(limit) }),
+        );
+    }
+
+    /// Sends the `amount - fee` NEAR of an already debited withdrawal to
+    /// `account`, settling the fee or the refund in `on_withdraw_resolved`.
+    fn transfer_withdrawal(
+        &self,
+        account: AccountId,
+        amount: u128,
+        fee: u128,
+        day: u64,
+    ) -> Promise {
+        Promise::new(account.clone())
+            .transfer(NearToken::from_yoctonear(amount - fee))
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(WITHDRAW_CALLBACK_GAS)
+                    .on_withdraw_resolved(account, U128(amount), U128(fee), day),
+            )
+    }
+
+    /// Credits a withdrawal fee to the treasury, less the reward pool's share.
+    fn credit_withdraw_fee(&mut self, fee: u128) {
+        let treasury_fee = fee - self.fund_reward_pool(fee, "withdrawal fee");
+        if treasury_fee > 0 {
+            let treasury = self.treasury.clone();
+            let treasury_balance = self.balance_of_internal(&treasury);
+            self.set_balance(&treasury, treasury_balance + treasury_fee);
+            self.emit_with_balances(
+                "withdraw_fee_collected",
+                json!({ "treasury_id": treasury, "amount": U128(treasury_fee) }),
+            );
+        }
+    }
+
+    /// Counts `amount` of NEAR leaving the contract against today's withdrawal
+    /// limit, rejecting it if the limit would be exceeded. Returns the day bucket
+    /// it was counted in.
+    fn record_outflow(&mut self, amount: u128) -> u64 {
+        let day = env::block_timestamp() / DAY_NS;
+        let (withdrawn, bucket) = self.withdrawn_today;
+        let withdrawn = if bucket == day { withdrawn } else { 0 } + amount;
+        assert!(
+            self.daily_withdraw_limit == 0 || withdrawn <= self.daily_withdraw_limit,
+            "{}",
+            errors::DAILY_LIMIT_EXCEEDED
+        );
+        self.withdrawn_today = (withdrawn, day);
+        day
+    }
+
+    /// Removes a failed payout of `amount` from the daily total it was counted
+    /// in. Totals of past days have already been reset, so only `day` matters.
+    fn reverse_outflow(&mut self, amount: u128, day: u64) {
+        let (withdrawn, bucket) = self.withdrawn_today;
+        if bucket == day {
+            self.withdrawn_today = (withdrawn.saturating_sub(amount), bucket);
+        }
+    }
+
+    fn internal_set_treasury(&mut self, treasury: AccountId) {
+        self.emit(
+            "treasury_updated",
+            json!({ "old_treasury": self.treasury, "new_treasury": treasury }),
+        );
+        self.treasury = treasury;
+    }
+
+    /// Whether `account` is the admin or has been granted `role`.
+    fn account_has_role(&self, account: &AccountId, role: Role) -> bool {
+        *account == self.admin || self.roles.contains(&(account.clone(), role))
+    }
+
+    /// Panics unless the caller holds `role`.
+    fn assert_role(&self, role: Role) {
+        assert!(
+            self.account_has_role(&env::predecessor_account_id(), role),
+            "Missing {:?} role",
+            role
+        );
+    }
+
+    fn assert_not_paused(&self) {
+        assert!(!self.paused, "{}", errors::CONTRACT_PAUSED);
+    }
+
+    /// Whether `account` is the admin or the treasury, which bypass transfer
+    /// restrictions so liquidity can still be seeded.
+    fn is_transfer_exempt(&self, account: &AccountId) -> bool {
+        *account == self.admin || *account == self.treasury
+    }
+
+    /// Whether `account` bypasses the transfer cooldown.
+    fn is_cooldown_exempt(&self, account: &AccountId) -> bool {
+        self.transfer_cooldown_seconds == 0 || self.is_transfer_exempt(account)
+    }
+
+    /// Rejects transfers from `sender` while transfers are disabled.
+    fn assert_transfers_enabled(&self, sender: &AccountId) {
+        assert!(
+            self.transfers_enabled || self.is_transfer_exempt(sender),
+            "{}",
+            errors::TRANSFERS_DISABLED
+        );
+    }
+
+    /// Rejects a transfer from `sender` inside its cooldown window, then records it.
+    fn enforce_transfer_cooldown(&mut self, sender: &AccountId) {
+        if self.is_cooldown_exempt(sender) {
+            return;
+        }
+        let now = env::block_timestamp();
+        if let Some(last) = self.last_transfer.get(sender) {
+            assert!(
+                now - last >= self.transfer_cooldown_seconds * 1_000_000_000,
+                "{}",
+                errors::TRANSFER_COOLDOWN
+            );
+        }
+        self.last_transfer.insert(sender, &now);
+    }
+
+    /// Total supply minus the treasury's liquid balance, the admin reserve and
+    /// the reward pool.
+    fn circulating_supply(&self) -> u128 {
+        self.total_supply
+            - self.balance_of_internal(&self.treasury)
+            - self.admin_reserve
+            - self.reward_pool
+    }
+
+    fn internal_queue_action(&mut self, action: TimelockedAction) -> u64 {
+        if let TimelockedAction::SetTimelockDelay { seconds } = &action {
+            assert!(
+                *seconds >= MIN_TIMELOCK_DELAY_SECONDS,
+                "Timelock delay below the minimum"
+            );
+        }
+        let id = self.next_action_id;
+        let execute_after = env::block_timestamp() + self.timelock_delay_seconds * 1_000_000_000;
+        self.emit(
+            "action_queued",
+            json!({ "action_id": id, "action": action, "execute_after": execute_after }),
+        );
+        self.queued_actions.insert(
+            &id,
+            &QueuedAction {
+                action,
+                execute_after,
+            },
+        );
+        self.next_action_id += 1;
+        id
+    }
+
+    fn internal_recover_near(&mut self, amount: u128) {
+        self.with_lock(|this| {
+            assert!(
+                amount <= this.unaccounted_near(),
+                "Amount exceeds the unaccounted NEAR"
+            );
+            let treasury = this.treasury.clone();
+            Promise::new(treasury.clone()).transfer(NearToken::from_yoctonear(amount));
+            this.emit(
+                "near_recovered",
+                json!({ "treasury_id": treasury, "amount": U128(amount) }),
+            );
+        })
+    }
+
+    /// NEAR held by the contract beyond storage costs, token backing, pending
+    /// mint refunds and NFT storage credit.
+    fn unaccounted_near(&self) -> u128 {
+        let storage_cost = env::storage_byte_cost().as_yoctonear() * env::storage_usage() as u128;
+        env::account_balance()
+            .as_yoctonear()
+            .saturating_sub(storage_cost)
+            .saturating_sub(self.total_supply)
+            .saturating_sub(self.total_pending_mint_refunds)
+            .saturating_sub(self.total_nft_storage_credits)
+    }
+
+    fn internal_release_reserve(&mut self, amount: u128) {
+        assert!(
+            amount <= self.admin_reserve,
+            "Amount exceeds the admin reserve"
+        );
+        self.admin_reserve -= amount;
+        let admin = self.admin.clone();
+        let balance = self.balance_of_internal(&admin);
+        self.set_balance(&admin, balance + amount);
+        self.emit_with_balances(
+            "admin_reserve_released",
+            json!({
+                "admin_id": admin,
+                "account_id": admin,
+                "amount": U128(amount),
+                "reserve": U128(self.admin_reserve),
+            }),
+        );
+    }
+
+    /// Stores a new NFT owned by `owner` and returns its ID, enforcing the NFT
+    /// supply cap, the metadata limit and the royalty cap.
+    fn internal_mint_nft(
+        &mut self,
+        owner: &AccountId,
+        creator: &AccountId,
+        metadata: String,
+        royalty_bps: Option<u16>,
+    ) -> u64 {
+        assert!(
+            self.nft_max_supply == 0 || self.sold_nft_count() < self.nft_max_supply,
+            "{}",
+            errors::NFT_SUPPLY_EXHAUSTED
+        );
+        assert!(
+            metadata.len() <= self.nft_max_metadata_len as usize,
+            "{}",
+            errors::METADATA_TOO_LONG
+        );
+        let royalty_bps = royalty_bps.unwrap_or(0);
+        assert!(royalty_bps <= MAX_ROYALTY_BPS, "Royalty too high");
+        let token_id = self.next_token_id;
+        let nft = Nft {
+            owner: owner.clone(),
+            creator: creator.clone(),
+            metadata,
+            royalty_bps,
+        };
+        self.nft_tokens.insert(&token_id, &nft);
+        self.add_token_to_owner(owner, token_id);
+        self.next_token_id += 1;
+        self.emit(
+            "nft_mint",
+            json!({
+                "owner_id": owner,
+                "creator_id": creator,
+                "token_id": token_id,
+                "metadata": nft.metadata,
+            }),
+        );
+        token_id
+    }
+
+    /// Runs `f` with the reentrancy flag set, rejecting nested entry. Wraps every
+    /// method that creates a `Promise` and every callback resolving one.
+    ///
+    /// On NEAR a function call runs to completion before any promise it creates
+    /// executes: transfers and cross-contract calls run in later receipts, and
+    /// their callbacks are separate calls. Nothing can re-enter a method
+    /// mid-execution, so the flag is cleared before any other call can observe
+    /// it and the guard is defensive, catching internal helpers that would nest
+    /// guarded sections. State consistency across the async gap comes from
+    /// debiting before the promise is created and crediting back in the
+    /// callback on failure, not from this flag.
+    fn with_lock<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        assert!(!self.locked, "Reentrant call");
+        self.locked = true;
+        let result = f(self);
+        self.locked = false;
+        result
+    }
+
+    /// Whether the contract holds a balance or stake entry for `account`.
+    fn has_account(&self, account: &AccountId) -> bool {
+        self.balances.get(account).is_some() || self.staked.get(account).is_some()
+    }
+
+    /// Reads an account's liquid balance. Unknown accounts read as 0: receiving
+    /// requires no registration.
+    fn balance_of_internal(&self, account: &AccountId) -> u128 {
+        self.balances.get(account).unwrap_or(0)
+    }
+
+    /// Reads the liquid balance of an account about to spend or stake it,
+    /// rejecting accounts the contract has never seen.
+    fn require_account(&self, account: &AccountId) -> u128 {
+        assert!(
+            self.has_account(account),
+            "{}",
+            errors::ACCOUNT_NOT_REGISTERED
+        );
+        self.balance_of_internal(account)
+    }
+
+    /// Writes an account's liquid balance. Every balance mutation goes through here
+    /// so the holder count and holder leaderboard never drift out of sync.
+    /// Maintaining the sorted leaderboard costs O(log n) storage reads and writes
+    /// per change, paid in exchange for cheap ordered reads.
+    fn set_balance(&mut self, account: &AccountId, new_balance: u128) {
+        let old_balance = self.balance_of_internal(account);
+        self.balances.insert(account, &new_balance);
+        if old_balance == new_balance {
+            return;
+        }
+        if old_balance > 0 {
+            self.holder_index.remove(&(old_balance, account.clone()));
+        } else {
+            self.holder_count += 1;
+        }
+        if new_balance > 0 {
+            self.holder_index
+                .insert(&(new_balance, account.clone()), &());
+        } else {
+            self.holder_count -= 1;
+        }
+    }
+
+    /// Sets `account`'s staked balance, keeping `total_staked` and `staker_count`
+    /// in sync. All staked balance writes should go through here.
+    fn set_staked(&mut self, account: &AccountId, new_staked: u128) {
+        let old_staked = self.staked.get(account).unwrap_or(0);
+        if new_staked == 0 {
+            self.staked.remove(account);
+        } else {
+            self.staked.insert(account, &new_staked);
+        }
+        self.total_staked = self.total_staked - old_staked + new_staked;
+        if old_staked == 0 && new_staked > 0 {
+            self.staker_count += 1;
+        } else if old_staked > 0 && new_staked == 0 {
+            self.staker_count -= 1;
+        }
+    }
+
+    /// Validates that `voter` may vote on `proposal`, locks in its voting weight
+    /// (including delegators that haven't taken part yet) and records the vote,
+    /// counting the voter in `proposal.voter_count`. Returns the weight to add
+    /// to the tally.
+    fn record_vote(
+        &mut self,
+        proposal: &mut Proposal,
+        voter: &AccountId,
+        support: bool,
+        option: Option<u32>,
+    ) -> u128 {
+        assert!(
+            self.delegations.get(voter).is_none(),
+            "{}",
+            errors::VOTING_POWER_DELEGATED
+        );
+        assert!(!proposal.cancelled, "{}", errors::PROPOSAL_CANCELLED);
+        assert!(!proposal.finalized, "{}", errors::PROPOSAL_FINALIZED);
+        assert!(
+            env::block_timestamp() < proposal.deadline,
+            "{}",
+            errors::VOTING_ENDED
+        );
+        assert!(
+            self.votes.get(&(proposal.id, voter.clone())).is_none(),
+            "{}",
+            errors::ALREADY_VOTED
+        );
+
+        // Count delegators that haven't already taken part, and lock them in.
+        let weight_source = if proposal.use_staked_weight {
+            &self.staked
+        } else {
+            &self.balances
+        };
+        let own_balance = weight_source.get(voter).unwrap_or(0);
+        if proposal.use_staked_weight {
+            assert!(
+                own_balance >= self.min_stake_to_vote,
+                "{}",
+                errors::STAKE_BELOW_VOTE_MINIMUM
+            );
+        }
+        let mut voter_balance = own_balance;
+        let mut backing = vec![(voter.clone(), own_balance)];
+        for delegator in self.delegators.get(voter).unwrap_or_default() {
+            let key = (proposal.id, delegator);
+            if self.votes.get(&key).is_none() {
+                let delegator_balance = weight_source.get(&key.1).unwrap_or(0);
+                voter_balance += delegator_balance;
+                backing.push((key.1.clone(), delegator_balance));
+                self.votes.insert(
+                    &key,
+                    &VoteRecord {
+                        support,
+                        option,
+                        weight: 0,
+                    },
+                );
+            }
+        }
+        assert!(voter_balance > 0, "{}", errors::NO_VOTING_POWER);
+        // Vote locks guard liquid balances; staked weight is not locked.
+        if self.vote_lock_enabled && !proposal.use_staked_weight {
+            self.lock_vote_backing(proposal.id, backing);
+        }
+        let weight = if proposal.quadratic {
+            isqrt(voter_balance)
+        } else {
+            voter_balance
+        };
+        // The recorded weight includes the bonus, so tallies never need redoing.
+        let weight = if env::block_timestamp() < proposal.early_vote_ends {
+            weight + apply_bps(weight, proposal.early_vote_bonus_bps, false)
+        } else {
+            weight
+        };
+        self.votes.insert(
+            &(proposal.id, voter.clone()),
+            &VoteRecord {
+                support,
+                option,
+                weight,
+            },
+        );
+        proposal.voter_count += 1;
+        proposal.raw_turnout += voter_balance;
+        weight
+    }
+
+    /// Records a binary vote of `voter` and adds its weight to the tally.
+    fn internal_vote(&mut self, voter: &AccountId, proposal_id: u64, support: bool) {
+        let mut proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect(errors::PROPOSAL_NOT_FOUND);
+        assert!(proposal.options.is_empty(), "{}", errors::USE_VOTE_OPTION);
+        let weight = self.record_vote(&mut proposal, voter, support, None);
+        if support {
+            proposal.votes_for += weight;
+        } else {
+            proposal.votes_against += weight;
+        }
+        self.proposals.insert(&proposal_id, &proposal);
+        self.emit(
+            "vote",
+            json!({
+                "account_id": voter,
+                "proposal_id": proposal_id,
+                "support": support,
+                "weight": U128(weight),
+            }),
+        );
+    }
+
+    /// Earliest time (ns) `proposal` may be finalized, ignoring the veto window.
+    fn grace_deadline(&self, proposal: &Proposal) -> u64 {
+        proposal.deadline + self.finalization_grace_seconds * 1_000_000_000
+    }
+
+    /// Validates and stores a new proposal created by the caller.
+    fn internal_propose(
+        &mut self,
+        description: String,
+        quadratic: Option<bool>,
+        options: Option<Vec<String>>,
+        use_staked_weight: Option<bool>,
+        action: Option<ProposalAction>,
+    ) {
+        assert!(
+            (MIN_DESCRIPTION_LEN..=MAX_DESCRIPTION_LEN).contains(&description.len()),
+            "Description must be between {} and {} bytes",
+            MIN_DESCRIPTION_LEN,
+            MAX_DESCRIPTION_LEN
+        );
+        let options = options.unwrap_or_default();
+        assert!(
+            options.is_empty() || (2..=MAX_PROPOSAL_OPTIONS).contains(&options.len()),
+            "Multiple choice proposals need between 2 and {} options",
+            MAX_PROPOSAL_OPTIONS
+        );
+        assert!(
+            action.is_none() || options.is_empty(),
+            "Only binary proposals can carry an action"
+        );
+        if let Some(action) = &action {
+            if let Err(reason) = self.validate_proposal_action(action) {
+                env::panic_str(&format!("Invalid proposal action: {}", reason));
+            }
+        }
+        assert!(
+            self.active_proposal_count < self.max_active_proposals,
+            "{}",
+            errors::TOO_MANY_ACTIVE_PROPOSALS
+        );
+        let supply_snapshot = self.circulating_supply();
+        let quorum = match self.quorum_mode {
+            QuorumMode::None => 0,
+            QuorumMode::Absolute { amount } => amount.0,
+            QuorumMode::Percentage { pct } => mul_div(supply_snapshot, pct as u128, 100),
+        };
+        let proposer = env::predecessor_account_id();
+        let now = env::block_timestamp();
+        let voting_period_ns = self
+            .voting_period_seconds
+            .max(self.min_vote_duration_seconds)
+            * 1_000_000_000;
+        let proposal = Proposal {
+            id: self.next_proposal_id,
+            description,
+            votes_for: 0,
+            votes_against: 0,
+            deadline: now + voting_period_ns,
+            finalized: false,
+            quadratic: quadratic.unwrap_or(false),
+            cancelled: false,
+            vote_counts: vec![0; options.len()],
+            options,
+            use_staked_weight: use_staked_weight.unwrap_or(false),
+            passed: false,
+            winning_option: None,
+            supply_snapshot,
+            quorum,
+            proposer: proposer.clone(),
+            action,
+            vetoed: false,
+            veto_reason: None,
+            endorsement_count: 0,
+            early_vote_bonus_bps: self.early_vote_bonus_bps,
+            early_vote_ends: now
+                + mul_div(
+                    voting_period_ns as u128,
+                    self.early_vote_window_bps as u128,
+                    10_000,
+                ) as u64,
+            voter_count: 0,
+            raw_turnout: 0,
+        };
+        self.proposals.insert(&self.next_proposal_id, &proposal);
+        self.proposal_ids.insert(&self.next_proposal_id);
+        self.open_proposal_ids.insert(&self.next_proposal_id);
+        self.active_proposal_count += 1;
+        self.emit(
+            "proposal_created",
+            json!({ "proposal_id": self.next_proposal_id, "proposer": proposer }),
+        );
+        self.next_proposal_id += 1;
+    }
+
+    /// Marks `proposal` finalized, caches its result, releases its vote locks
+    /// and emits the result. Callers must check it isn't finalized already.
+    fn internal_finalize(&mut self, mut proposal: Proposal) {
+        let winner = winning_option(&proposal.vote_counts);
+        proposal.passed = proposal.raw_turnout >= proposal.quorum
+            && match winner {
+                Some(index) => proposal.vote_counts[index] > 0,
+                None => proposal.votes_for > proposal.votes_against,
+            };
+        proposal.winning_option = winner.map(|index| index as u32);
+        proposal.finalized = true;
+        self.proposals.insert(&proposal.id, &proposal);
+        self.open_proposal_ids.remove(&proposal.id);
+        self.active_proposal_count -= 1;
+        self.release_vote_locks(proposal.id);
+        if let Some(action) = proposal.action.clone() {
+            if proposal.passed && !proposal.vetoed {
+                self.execute_proposal_action(proposal.id, action);
+            }
+        }
+        self.emit(
+            "proposal_finalized",
+            json!({
+                "proposal_id": proposal.id,
+                "votes_for": U128(proposal.votes_for),
+                "votes_against": U128(proposal.votes_against),
+                "winning_option": proposal.winning_option,
+                "passed": proposal.passed,
+            }),
+        );
+    }
+
+    /// Credits `amount` tokens minted for a deposit to `account` and, if it
+    /// registered a referrer, a 1% bonus to that referrer.
+    fn internal_mint(&mut self, account: &AccountId, amount: u128, memo: &str) {
+        let current_balance = self.balance_of_internal(account);
+        self.set_balance(account, current_balance + amount);
+        self.total_supply += amount;
+        self.total_deposit_minted += amount;
+
+        // Grant a 1% bonus to a registered referrer, if any, while the referral
+        // supply cap allows it.
+        if let Some((referrer, bonus)) = self.referral_bonus_for(account, amount) {
+            if bonus > 0 {
+                self.credit_referral_bonus(&referrer, bonus);
+            } else if apply_bps(amount, REFERRAL_BONUS_BPS, false) > 0 {
+                self.emit(
+                    "referral_bonus_skipped",
+                    json!({ "referrer_id": referrer, "account_id": account, "reason": "cap reached" }),
+                );
+            }
+        }
+
+        self.emit_ft(
+            "ft_mint",
+            json!({ "owner_id": account, "amount": U128(amount), "memo": memo }),
+        );
+    }
+
+    /// Sends `amount` of a mint deposit back to `account`. If the transfer fails,
+    /// `on_mint_refund_resolved` keeps it claimable via `claim_mint_refund`.
+    fn refund_mint_deposit(&self, account: &AccountId, amount: u128) -> Promise {
+        Promise::new(account.clone())
+            .transfer(NearToken::from_yoctonear(amount))
+            .then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(MINT_REFUND_CALLBACK_GAS)
+                    .on_mint_refund_resolved(account.clone(), U128(amount)),
+            )
+    }
+
+    /// Returns how many tokens `mint` credits for `deposit`: all of it, or what
+    /// is left under `max_supply` (the rest is refunded). Rejects a deposit below
+    /// the minimum or a credit that would create dust.
+    fn mint_credit(&self, account: &AccountId, deposit: u128) -> u128 {
+        // Require a minimum deposit to cover storage fees.
+        assert!(
+            deposit >= self.min_mint_deposit,
+            "{}",
+            errors::DEPOSIT_TOO_LOW
+        );
+        let credited = deposit.min(self.mintable_supply());
+        if credited > 0 {
+            self.assert_not_dust(account, credited);
+        }
+        credited
+    }
+
+    /// Tokens that can still be minted before reaching `max_supply`.
+    fn mintable_supply(&self) -> u128 {
+        if self.max_supply == 0 {
+            return u128::MAX;
+        }
+        self.max_supply.saturating_sub(self.total_supply)
+    }
+
+    /// The referrer of `account` and the bonus it earns when `amount` is minted
+    /// to `account`: 1%, limited by the referral supply cap.
+    fn referral_bonus_for(&self, account: &AccountId, amount: u128) -> Option<(AccountId, u128)> {
+        self.referrals
+            .get(account)
+            .map(|referrer| self.resolve_merged(referrer))
+            // Bonuses round down, as they are newly minted.
+            .map(|referrer| {
+                let bonus = apply_bps(amount, REFERRAL_BONUS_BPS, false);
+                (referrer, self.capped_referral_bonus(bonus))
+            })
+    }
+
+    /// Follows `merge_accounts` redirects from `account` to the account that
+    /// now holds its positions.
+    fn resolve_merged(&self, mut account: AccountId) -> AccountId {
+        while let Some(target) = self.merged_into.get(&account) {
+            account = target;
+        }
+        account
+    }
+
+    /// Limits a referral `bonus` to what remains under `max_referral_supply`.
+    fn capped_referral_bonus(&self, bonus: u128) -> u128 {
+        let bonus = bonus.min(self.mintable_supply());
+        if self.max_referral_supply == 0 {
+            return bonus;
+        }
+        bonus.min(
+            self.max_referral_supply
+                .saturating_sub(self.total_referral_minted),
+        )
+    }
+
+    /// Mints a referral `bonus` to `referrer`, to its stake if it opted in.
+    fn credit_referral_bonus(&mut self, referrer: &AccountId, bonus: u128) {
+        let to_stake = self.referral_to_stake.get(referrer).unwrap_or(false);
+        if to_stake {
+            self.credit_stake(referrer, bonus);
+        } else {
+            let ref_balance = self.balance_of_internal(referrer);
+            self.set_balance(referrer, ref_balance + bonus);
+        }
+        self.total_supply += bonus;
+        self.total_referral_minted += bonus;
+        let earnings = self.referral_earnings.get(referrer).unwrap_or(0) + bonus;
+        self.referral_earnings.insert(referrer, &earnings);
+        let memo = if to_stake {
+            "referral bonus (staked)"
+        } else {
+            "referral bonus"
+        };
+        self.emit_ft(
+            "ft_mint",
+            json!({ "owner_id": referrer, "amount": U128(bonus), "memo": memo }),
+        );
+    }
+
+    /// Moves rewards accrued on `account`'s current stake into `unclaimed_rewards`
+    /// and restarts accrual from now. Called before every change to a stake so
+    /// the new principal never earns for time already elapsed. Returns the
+    /// account's claimable rewards.
+    fn settle_rewards(&mut self, account: &AccountId) -> u128 {
+        let now = env::block_timestamp();
+        let pending = self.accrued(account, now);
+        if pending > 0 {
+            self.unclaimed_rewards.insert(account, &pending);
+        }
+        if self.staked.get(account).unwrap_or(0) > 0 {
+            let total = self.stake_seconds_at(account, now);
+            self.stake_seconds.insert(account, &total);
+        }
+        self.last_claim.insert(account, &now);
+        pending
+    }
+
+    /// `account`'s settled stake-seconds plus those its current stake has
+    /// accumulated since `last_claim`.
+    fn stake_seconds_at(&self, account: &AccountId, now: u64) -> (u128, u128) {
+        let settled = self.stake_seconds.get(account).unwrap_or((0, 0));
+        match self.last_claim.get(account) {
+            Some(last_claim) => add_stake_seconds(
+                settled,
+                self.staked.get(account).unwrap_or(0),
+                now.saturating_sub(last_claim),
+            ),
+            None => settled,
+        }
+    }
+
+    /// Adds freshly minted `amount` directly to `account`'s stake. Rewards accrued
+    /// on the previous stake are carried into `unclaimed_rewards` first so the
+    /// new amount only earns from now on. Stake limits and the slashing window
+    /// are left untouched, since the account didn't initiate the stake.
+    fn credit_stake(&mut self, account: &AccountId, amount: u128) {
+        self.settle_rewards(account);
+        if self.stake_started.get(account).is_none() {
+            self.stake_started.insert(account, &env::block_timestamp());
+        }
+        let current_staked = self.staked.get(account).unwrap_or(0);
+        self.set_staked(account, current_staked + amount);
+    }
+
+    /// Locks each account's backing balance for `proposal_id`.
+    fn lock_vote_backing(&mut self, proposal_id: u64, backing: Vec<(AccountId, u128)>) {
+        let mut locks = self.vote_locks.get(&proposal_id).unwrap_or_default();
+        for (account, amount) in backing {
+            if amount == 0 {
+                continue;
+            }
+            let locked = self.locked_balances.get(&account).unwrap_or(0) + amount;
+            self.locked_balances.insert(&account, &locked);
+            locks.push((account, amount));
+        }
+        self.vote_locks.insert(&proposal_id, &locks);
+    }
+
+    /// Releases every lock taken by votes on `proposal_id`.
+    fn release_vote_locks(&mut self, proposal_id: u64) {
+        for (account, amount) in self.vote_locks.remove(&proposal_id).unwrap_or_default() {
+            let locked = self
+                .locked_balances
+                .get(&account)
+                .unwrap_or(0)
+                .saturating_sub(amount);
+            if locked == 0 {
+                self.locked_balances.remove(&account);
+            } else {
+                self.locked_balances.insert(&account, &locked);
+            }
+        }
+    }
+
+    /// Rejects crediting `amount` to an account without a balance if it would
+    /// leave it holding less than `dust_threshold`.
+    fn assert_not_dust(&self, account: &AccountId, amount: u128) {
+        let balance = self.balance_of_internal(account);
+        assert!(
+            balance > 0 || amount >= self.dust_threshold,
+            "{}",
+            errors::BELOW_DUST_THRESHOLD
+        );
+    }
+
+    /// Rejects spending `amount` out of `balance` if it would dip into the
+    /// balance locked by open votes.
+    fn assert_unlocked(&self, account: &AccountId, balance: u128, amount: u128) {
+        let locked = self.locked_balances.get(account).unwrap_or(0);
+        assert!(
+            amount <= balance.saturating_sub(locked),
+            "{}",
+            errors::BALANCE_LOCKED
+        );
+    }
+
+    /// Checks that `account` may stake `amount` of its liquid balance and returns
+    /// its current (available, staked) balances.
+    fn assert_can_stake(&self, account: &AccountId, amount: u128) -> (u128, u128) {
+        assert!(amount > 0, "{}", errors::AMOUNT_NOT_POSITIVE);
+        let available = self.require_account(account);
+        assert!(
+            available >= amount,
+            "{}",
+            errors::INSUFFICIENT_BALANCE_TO_STAKE
+        );
+        self.assert_unlocked(account, available, amount);
+        let current_staked = self.staked.get(account).unwrap_or(0);
+        self.assert_stake_limits(current_staked, amount);
+        (available, current_staked)
+    }
+
+    /// Moves `amount` of `account`'s liquid balance into its stake.
+    fn internal_stake(&mut self, account: &AccountId, amount: u128) {
+        let (available, current_staked) = self.assert_can_stake(account, amount);
+        self.settle_rewards(account);
+        self.set_balance(account, available - amount);
+        self.set_staked(account, current_staked + amount);
+        self.stake_started.insert(account, &env::block_timestamp());
+    }
+
+    /// Rejects adding `amount` to a stake of `current_staked` if it violates the
+    /// minimum stake amount or the per-account maximum.
+    fn assert_stake_limits(&self, current_staked: u128, amount: u128) {
+        assert!(amount >= self.min_stake, "{}", errors::STAKE_BELOW_MINIMUM);
+        assert!(
+            self.max_stake_per_account == 0
+                || current_staked + amount <= self.max_stake_per_account,
+            "{}",
+            errors::STAKE_ABOVE_MAXIMUM
+        );
+    }
+
+    /// Takes `amount` (or all) of `account`'s claimable rewards from the reward
+    /// pool, minting whatever the pool cannot cover, and returns the amount for
+    /// the caller to credit. The unclaimed remainder is carried forward instead
+    /// of rewinding `last_claim`, so partial claims never lose or double-count
+    /// accrued rewards. The minted part may not exceed `max_supply`.
+    fn take_rewards(&mut self, account: &AccountId, amount: Option<u128>) -> u128 {
+        let claimable = self.settle_rewards(account);
+        let reward = amount.unwrap_or(claimable);
+        assert!(reward > 0, "{}", errors::NO_REWARDS);
+        assert!(reward <= claimable, "{}", errors::EXCEEDS_CLAIMABLE);
+        self.unclaimed_rewards
+            .insert(account, &(claimable - reward));
+        let from_pool = reward.min(self.reward_pool);
+        assert!(
+            reward - from_pool <= self.mintable_supply(),
+            "{}",
+            errors::MAX_SUPPLY_EXCEEDED
+        );
+        if from_pool > 0 {
+            self.reward_pool -= from_pool;
+            self.emit(
+                "reward_pool_drawn",
+                json!({
+                    "account_id": account,
+                    "amount": U128(from_pool),
+                    "pool": U128(self.reward_pool),
+                }),
+            );
+        }
+        self.total_supply += reward - from_pool;
+        self.total_reward_minted += reward - from_pool;
+        reward
+    }
+
+    /// Moves `fee_to_pool_bps` of `amount` (tokens already taken from an account)
+    /// into the reward pool and returns the share moved.
+    fn fund_reward_pool(&mut self, amount: u128, source: &str) -> u128 {
+        // The pool's share rounds down; the rest keeps its original destination.
+        let funded = apply_bps(amount, self.fee_to_pool_bps, false);
+        if funded > 0 {
+            self.reward_pool += funded;
+            self.emit(
+                "reward_pool_funded",
+                json!({ "amount": U128(funded), "source": source, "pool": U128(self.reward_pool) }),
+            );
+        }
+        funded
+    }
+
+    /// Takes `staker`'s rewards (all of them if `amount` is `None`) and credits
+    /// them to `beneficiary`'s balance. Returns the amount claimed.
+    fn internal_claim(
+        &mut self,
+        staker: &AccountId,
+        beneficiary: &AccountId,
+        amount: Option<u128>,
+    ) -> u128 {
+        let pool_before = self.reward_pool;
+        let reward = self.take_rewards(staker, amount);
+        let from_pool = pool_before - self.reward_pool;
+        let available = self.balance_of_internal(beneficiary);
+        self.set_balance(beneficiary, available + reward);
+        if from_pool > 0 {
+            self.emit_with_balances(
+                "reward_pool_paid",
+                json!({ "account_id": beneficiary, "staker_id": staker, "amount": U128(from_pool) }),
+            );
+        }
+        // Only the part not covered by the pool is newly minted.
+        if reward > from_pool {
+            self.emit_ft(
+                "ft_mint",
+                json!({
+                    "owner_id": beneficiary,
+                    "amount": U128(reward - from_pool),
+                    "memo": "staking reward",
+                }),
+            );
+        }
+        reward
+    }
+
+    /// Own balance plus the balances of all accounts delegating to `account`.
+    fn voting_power(&self, account: &AccountId) -> u128 {
+        let own = self.balance_of_internal(account);
+        let delegated: u128 = self
+            .delegators
+            .get(account)
+            .unwrap_or_default()
+            .iter()
+            .map(|delegator| self.balance_of_internal(delegator))
+            .sum();
+        own + delegated
+    }
+
+    /// Removes `delegator`'s delegation and returns the former delegate.
+    fn remove_delegation(&mut self, delegator: &AccountId) -> AccountId {
+        let delegate = self
+            .delegations
+            .remove(delegator)
+            .expect(errors::NO_DELEGATION);
+        let mut delegators = self.delegators.get(&delegate).unwrap_or_default();
+        delegators.retain(|account| account != delegator);
+        if delegators.is_empty() {
+            self.delegators.remove(&delegate);
+        } else {
+            self.delegators.insert(&delegate, &delegators);
+        }
+        delegate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::mock::MockAction;
+    use near_sdk::test_utils::{accounts, get_created_receipts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    const ONE: u128 = 1_000_000_000_000_000_000_000_000;
+    const SECOND: u64 = 1_000_000_000;
+
+    fn account(name: &str) -> AccountId {
+        format!("{}.near", name).parse().unwrap()
+    }
+
+    /// A call by `caller` attaching `deposit` yoctoNEAR at `timestamp` (ns).
+    fn context(caller: &AccountId, deposit: u128, timestamp: u64) -> VMContextBuilder {
+        let mut context = VMContextBuilder::new();
+        context
+            .current_account_id(account("memecoin"))
+            .predecessor_account_id(caller.clone())
+            .signer_account_id(caller.clone())
+            .attached_deposit(NearToken::from_yoctonear(deposit))
+            .block_timestamp(timestamp);
+        context
+    }
+
+    /// Sets up the next call with `context`. Contract storage carries over
+    /// between calls.
+    fn set_context(caller: &AccountId, deposit: u128, timestamp: u64) {
+        testing_env!(context(caller, deposit, timestamp).build());
+    }
+
+    /// Deploys the contract with `accounts(0)` as admin and treasury, on fresh
+    /// storage.
+    fn setup() -> Contract {
+        near_sdk::mock::with_mocked_blockchain(|blockchain| blockchain.take_storage());
+        set_context(&accounts(0), 0, 0);
+        Contract::new()
+    }
+
+    /// Mints `amount` tokens to `holder` by depositing NEAR.
+    fn mint(contract: &mut Contract, holder: &AccountId, amount: u128) {
+        set_context(holder, amount, 0);
+        contract.mint();
+    }
+
+    /// NEAR transfers created by the last call, as (receiver, amount) pairs.
+    fn transfers() -> Vec<(AccountId, u128)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                let receiver = receipt.receiver_id;
+                receipt
+                    .actions
+                    .into_iter()
+                    .filter_map(move |action| match action {
+                        MockAction::Transfer { deposit, .. } => {
+                            Some((receiver.clone(), deposit.as_yoctonear()))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    /// Function calls created by the last call, as (receiver, method) pairs.
+    fn function_calls() -> Vec<(AccountId, String)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                let receiver = receipt.receiver_id;
+                receipt
+                    .actions
+                    .into_iter()
+                    .filter_map(move |action| match action {
+                        MockAction::FunctionCallWeight { method_name, .. } => {
+                            Some((receiver.clone(), String::from_utf8(method_name).unwrap()))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    /// Events logged since the last `set_context`, as parsed NEP-297 JSON.
+    fn events() -> Vec<Value> {
+        get_logs()
+            .iter()
+            .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+            .map(|event| near_sdk::serde_json::from_str(event).unwrap())
+            .collect()
+    }
+
+    /// The data of the single entry of the first `name` event since the last
+    /// `set_context`.
+    fn event_data(name: &str) -> Value {
+        let event = events()
+            .into_iter()
+            .find(|event| event["event"] == name)
+            .unwrap_or_else(|| panic!("no {} event", name));
+        event["data"][0].clone()
+    }
+
+    /// Runs `f` and returns its panic message. Fails if `f` does not panic.
+    fn panic_message<T>(f: impl FnOnce() -> T) -> String {
+        let payload = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+            .err()
+            .expect("expected a panic");
+        payload
+            .downcast_ref::<String>()
+            .cloned()
+            .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+            .unwrap_or_default()
+    }
+
+    /// Creates a binary proposal as the admin at time 0 and returns its ID.
+    fn propose(contract: &mut Contract, quadratic: bool) -> u64 {
+        set_context(&accounts(0), 0, 0);
+        contract.propose(
+            "Adopt the new logo".to_string(),
+            Some(quadratic),
+            None,
+            None,
+            None,
+        );
+        contract.next_proposal_id - 1
+    }
+
+    fn vote(contract: &mut Contract, voter: &AccountId, proposal_id: u64, support: bool) {
+        set_context(voter, 0, SECOND);
+        contract.vote(proposal_id, support);
+    }
+
+    /// Whale votes against, five small holders vote for, and returns the tallies.
+    fn whale_vs_small_holders(quadratic: bool) -> ProposalView {
+        let mut contract = setup();
+        mint(&mut contract, &account("whale"), 10_000 * ONE);
+        for i in 0..5 {
+            mint(&mut contract, &account(&format!("small{}", i)), 1_000 * ONE);
+        }
+        let id = propose(&mut contract, quadratic);
+        vote(&mut contract, &account("whale"), id, false);
+        for i in 0..5 {
+            vote(&mut contract, &account(&format!("small{}", i)), id, true);
+        }
+        contract.get_proposal(id).unwrap()
+    }
+
+    #[test]
+    fn isqrt_rounds_down() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(1), 1);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(u128::MAX), u64::MAX as u128);
+    }
+
+    #[test]
+    fn linear_voting_lets_the_whale_win() {
+        let view = whale_vs_small_holders(false);
+        assert!(!view.quadratic);
+        assert_eq!(view.votes_against.0, 10_000 * ONE);
+        assert_eq!(view.votes_for.0, 5_000 * ONE);
+    }
+
+    #[test]
+    fn quadratic_voting_lets_the_small_holders_win() {
+        let view = whale_vs_small_holders(true);
+        assert!(view.quadratic);
+        assert_eq!(view.votes_against.0, isqrt(10_000 * ONE));
+        assert_eq!(view.votes_for.0, 5 * isqrt(1_000 * ONE));
+        assert!(view.votes_for.0 > view.votes_against.0);
+        assert_eq!(view.raw_turnout.0, 15_000 * ONE);
+    }
+
+    #[test]
+    fn vote_weight_ignores_later_balance_changes() {
+        let mut contract = setup();
+        let voter = account("voter");
+        mint(&mut contract, &voter, 100 * ONE);
+        let id = propose(&mut contract, false);
+        vote(&mut contract, &voter, id, true);
+        mint(&mut contract, &voter, 900 * ONE);
+        assert_eq!(contract.get_balance(voter.clone()).0, 1_000 * ONE);
+        assert_eq!(contract.get_proposal(id).unwrap().votes_for.0, 100 * ONE);
+        assert_eq!(contract.get_vote(id, voter).unwrap().weight.0, 100 * ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "E40")]
+    fn revoting_after_a_top_up_is_rejected() {
+        let mut contract = setup();
+        let voter = account("voter");
+        mint(&mut contract, &voter, 100 * ONE);
+        let id = propose(&mut contract, false);
+        vote(&mut contract, &voter, id, true);
+        mint(&mut contract, &voter, 900 * ONE);
+        vote(&mut contract, &voter, id, true);
+    }
+
+    /// Mints `amount` tokens to `staker` and stakes all of them at time 0.
+    fn mint_and_stake(contract: &mut Contract, staker: &AccountId, amount: u128) {
+        mint(contract, staker, amount);
+        contract.stake(U128(amount));
+    }
+
+    #[test]
+    fn compounding_grows_the_stake_geometrically() {
+        let mut contract = setup();
+        let staker = account("staker");
+        mint_and_stake(&mut contract, &staker, 1_000 * ONE);
+        let mut expected = 1_000 * ONE;
+        for year in 1..=3 {
+            set_context(&staker, 0, year * YEAR_NS as u64);
+            contract.compound_rewards();
+            expected += expected * DEFAULT_REWARD_RATE_BPS as u128 / 10_000;
+            assert_eq!(
+                contract.get_account_summary(staker.clone()).staked.0,
+                expected
+            );
+        }
+        assert_eq!(expected, 1_157_625 * ONE / 1_000);
+        assert_eq!(contract.get_claimable_rewards(staker).0, 0);
+    }
+
+    #[test]
+    fn compounding_stops_at_the_max_stake() {
+        let mut contract = setup();
+        let staker = account("staker");
+        mint_and_stake(&mut contract, &staker, 1_000 * ONE);
+        set_context(&accounts(0), 0, 0);
+        contract.set_stake_limits(U128(0), U128(1_020 * ONE));
+        set_context(&staker, 0, YEAR_NS as u64);
+        contract.compound_rewards();
+        assert_eq!(
+            contract.get_account_summary(staker.clone()).staked.0,
+            1_020 * ONE
+        );
+        assert_eq!(contract.get_claimable_rewards(staker).0, 30 * ONE);
+    }
+
+    #[test]
+    fn top_tipper_tracks_the_largest_total_after_each_tip() {
+        let mut contract = setup();
+        let tippers = [account("ann"), account("ben"), account("cat")];
+        for tipper in &tippers {
+            mint(&mut contract, tipper, 100 * ONE);
+        }
+        let steps = [
+            (0, 10, 0),
+            (1, 20, 1),
+            (2, 15, 1),
+            (0, 15, 0),
+            (2, 15, 2),
+            (1, 5, 2),
+        ];
+        for (tipper, amount, leader) in steps {
+            set_context(&tippers[tipper], 0, 0);
+            contract.tip(account("receiver"), U128(amount * ONE));
+            assert_eq!(contract.get_top_tipper(), Some(tippers[leader].clone()));
+        }
+    }
+
+    /// Deploys the contract with a 10 token minimum stake and a 100 token
+    /// maximum per account, and gives `staker` 200 liquid tokens.
+    fn setup_stake_limits(staker: &AccountId) -> Contract {
+        let mut contract = setup();
+        contract.set_stake_limits(U128(10 * ONE), U128(100 * ONE));
+        mint(&mut contract, staker, 200 * ONE);
+        contract
+    }
+
+    #[test]
+    fn stakes_at_the_limits_are_accepted() {
+        let staker = account("staker");
+        let mut contract = setup_stake_limits(&staker);
+        assert_eq!(contract.get_min_stake().0, 10 * ONE);
+        assert_eq!(contract.get_max_stake_per_account().0, 100 * ONE);
+        contract.stake(U128(10 * ONE));
+        contract.stake(U128(90 * ONE));
+        assert_eq!(contract.get_account_summary(staker).staked.0, 100 * ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "E29")]
+    fn stakes_below_the_minimum_are_rejected() {
+        let staker = account("staker");
+        let mut contract = setup_stake_limits(&staker);
+        contract.stake(U128(10 * ONE - 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "E30")]
+    fn stakes_above_the_maximum_are_rejected() {
+        let staker = account("staker");
+        let mut contract = setup_stake_limits(&staker);
+        contract.stake(U128(90 * ONE));
+        contract.stake(U128(10 * ONE + 1));
+    }
+
+    /// Deploys the contract with a 60 second transfer cooldown and gives `sender`
+    /// 100 tokens, then tips once at `start`.
+    fn setup_cooldown(sender: &AccountId, start: u64) -> Contract {
+        let mut contract = setup();
+        contract.set_transfer_cooldown(60);
+        mint(&mut contract, sender, 100 * ONE);
+        set_context(sender, 0, start);
+        contract.tip(account("receiver"), U128(ONE));
+        contract
+    }
+
+    #[test]
+    fn transfers_resume_at_the_cooldown_boundary() {
+        let sender = account("sender");
+        let mut contract = setup_cooldown(&sender, SECOND);
+        assert_eq!(contract.get_next_transfer_time(sender.clone()), 61 * SECOND);
+        set_context(&sender, 0, 61 * SECOND);
+        contract.tip(account("receiver"), U128(ONE));
+        assert_eq!(contract.get_next_transfer_time(sender), 121 * SECOND);
+    }
+
+    #[test]
+    #[should_panic(expected = "E13")]
+    fn transfers_inside_the_cooldown_are_rejected() {
+        let sender = account("sender");
+        let mut contract = setup_cooldown(&sender, SECOND);
+        set_context(&sender, 0, 61 * SECOND - 1);
+        contract.tip(account("receiver"), U128(ONE));
+    }
+
+    #[test]
+    fn the_admin_is_exempt_from_the_cooldown() {
+        let admin = accounts(0);
+        let mut contract = setup_cooldown(&admin, SECOND);
+        contract.tip(account("receiver"), U128(ONE));
+        assert_eq!(contract.get_next_transfer_time(admin), 0);
+    }
+
+    #[test]
+    fn nft_mint_refunds_the_deposit_above_the_storage_cost() {
+        let mut contract = setup();
+        let minter = account("minter");
+        set_context(&minter, ONE, 0);
+        let initial_storage = env::storage_usage();
+        contract.nft_mint("{\"name\":\"Doge\"}".to_string(), None, None);
+        let storage_cost = env::storage_byte_cost().as_yoctonear()
+            * (env::storage_usage() - initial_storage) as u128;
+        assert_eq!(transfers(), vec![(minter, ONE - storage_cost)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "yoctoNEAR short")]
+    fn nft_mint_rejects_a_deposit_below_the_storage_cost() {
+        let mut contract = setup();
+        set_context(&account("minter"), 1, 0);
+        contract.nft_mint("{\"name\":\"Doge\"}".to_string(), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "E57")]
+    fn nft_mint_rejects_over_long_metadata() {
+        let mut contract = setup();
+        contract.set_nft_max_metadata_len(16);
+        set_context(&account("minter"), ONE, 0);
+        contract.nft_mint("x".repeat(17), None, None);
+    }
+
+    #[test]
+    fn stake_from_rewards_restakes_part_and_keeps_the_rest_claimable() {
+        let mut contract = setup();
+        let staker = account("staker");
+        mint_and_stake(&mut contract, &staker, 1_000 * ONE);
+        set_context(&staker, 0, YEAR_NS as u64);
+        assert_eq!(contract.get_claimable_rewards(staker.clone()).0, 50 * ONE);
+        contract.stake_from_rewards(U128(20 * ONE));
+        let summary = contract.get_account_summary(staker.clone());
+        assert_eq!(summary.staked.0, 1_020 * ONE);
+        assert_eq!(summary.balance.0, 0);
+        assert_eq!(contract.get_claimable_rewards(staker.clone()).0, 30 * ONE);
+        contract.claim_rewards(None);
+        assert_eq!(contract.get_balance(staker).0, 30 * ONE);
+        assert_eq!(contract.get_total_supply().0, 1_050 * ONE);
+    }
+
+    #[test]
+    fn proposals_take_the_voting_period_set_at_creation() {
+        let mut contract = setup();
+        assert_eq!(contract.get_voting_period(), DEFAULT_VOTING_PERIOD_SECONDS);
+        contract.set_voting_period(3 * 24 * 60 * 60);
+        let short = propose(&mut contract, false);
+        contract.set_voting_period(MAX_VOTING_PERIOD_SECONDS);
+        let long = propose(&mut contract, false);
+        assert_eq!(contract.get_voting_period(), MAX_VOTING_PERIOD_SECONDS);
+        assert_eq!(
+            contract.get_proposal(short).unwrap().deadline,
+            3 * 24 * 60 * 60 * SECOND
+        );
+        assert_eq!(
+            contract.get_proposal(long).unwrap().deadline,
+            MAX_VOTING_PERIOD_SECONDS * SECOND
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Voting period must be between 1 second and 90 days")]
+    fn voting_periods_over_90_days_are_rejected() {
+        let mut contract = setup();
+        contract.set_voting_period(MAX_VOTING_PERIOD_SECONDS + 1);
+    }
+
+    /// Deploys the contract and gives `holder` 100 liquid and 10 staked tokens.
+    fn setup_holder(holder: &AccountId) -> Contract {
+        let mut contract = setup();
+        mint(&mut contract, holder, 110 * ONE);
+        contract.stake(U128(10 * ONE));
+        contract
+    }
+
+    #[test]
+    #[should_panic(expected = "E11")]
+    fn tipping_yourself_is_rejected() {
+        let holder = account("holder");
+        let mut contract = setup_holder(&holder);
+        contract.tip(holder, U128(ONE));
+    }
+
+    #[test]
+    #[should_panic(expected = "E2: Amount must be positive")]
+    fn zero_tips_are_rejected() {
+        let mut contract = setup_holder(&account("holder"));
+        contract.tip(account("receiver"), U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "E2: Amount must be positive")]
+    fn zero_stakes_are_rejected() {
+        let mut contract = setup_holder(&account("holder"));
+        contract.stake(U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "E2: Amount must be positive")]
+    fn zero_unstakes_are_rejected() {
+        let mut contract = setup_holder(&account("holder"));
+        contract.unstake(U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "E2: Amount must be positive")]
+    fn zero_burns_are_rejected() {
+        let mut contract = setup_holder(&account("holder"));
+        contract.burn(U128(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "E2: Amount must be positive")]
+    fn zero_withdrawals_are_rejected() {
+        let mut contract = setup_holder(&account("holder"));
+        contract.withdraw(U128(0));
+    }
+
+    #[test]
+    fn mints_at_the_new_minimum_deposit_are_accepted() {
+        let mut contract = setup();
+        assert_eq!(contract.get_min_mint_deposit().0, DEFAULT_MIN_MINT_DEPOSIT);
+        contract.set_min_mint_deposit(U128(ONE));
+        assert_eq!(contract.get_min_mint_deposit().0, ONE);
+        mint(&mut contract, &account("minter"), ONE);
+        assert_eq!(contract.get_balance(account("minter")).0, ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "E8")]
+    fn mints_below_the_new_minimum_deposit_are_rejected() {
+        let mut contract = setup();
+        contract.set_min_mint_deposit(U128(ONE));
+        mint(&mut contract, &account("minter"), ONE - 1);
+    }
+
+    /// Mints 100 tokens to a minter referred by `referrer`, who has chosen the
+    /// given payout mode.
+    fn mint_referred(to_stake: bool) -> (Contract, AccountId) {
+        let mut contract = setup();
+        let referrer = account("referrer");
+        set_context(&referrer, 0, 0);
+        contract.set_referral_payout_mode(to_stake);
+        let minter = account("minter");
+        set_context(&minter, 0, 0);
+        contract.register_referral(referrer.clone());
+        mint(&mut contract, &minter, 100 * ONE);
+        (contract, referrer)
+    }
+
+    #[test]
+    fn referral_bonuses_are_liquid_by_default() {
+        let (contract, referrer) = mint_referred(false);
+        let summary = contract.get_account_summary(referrer.clone());
+        assert_eq!(summary.balance.0, ONE);
+        assert_eq!(summary.staked.0, 0);
+        assert_eq!(contract.get_referral_earnings(referrer).0, ONE);
+        assert_eq!(contract.get_total_supply().0, 101 * ONE);
+    }
+
+    #[test]
+    fn referral_bonuses_can_be_staked() {
+        let (mut contract, referrer) = mint_referred(true);
+        assert!(contract.get_referral_payout_mode(referrer.clone()));
+        let summary = contract.get_account_summary(referrer.clone());
+        assert_eq!(summary.balance.0, 0);
+        assert_eq!(summary.staked.0, ONE);
+        assert_eq!(contract.get_total_supply().0, 101 * ONE);
+        // The staked bonus earns rewards from the mint onwards.
+        set_context(&referrer, 0, YEAR_NS as u64);
+        assert_eq!(contract.get_claimable_rewards(referrer.clone()).0, ONE / 20);
+        contract.claim_rewards(None);
+        assert_eq!(contract.get_balance(referrer).0, ONE / 20);
+    }
+
+    /// Gives `owner` 100 tokens and approves `spender` for 40 of them.
+    fn setup_allowance(owner: &AccountId, spender: &AccountId) -> Contract {
+        let mut contract = setup();
+        mint(&mut contract, owner, 100 * ONE);
+        contract.approve(spender.clone(), U128(40 * ONE));
+        contract
+    }
+
+    #[test]
+    fn burn_from_spends_the_allowance_and_the_owner_balance() {
+        let (owner, spender) = (account("owner"), account("spender"));
+        let mut contract = setup_allowance(&owner, &spender);
+        set_context(&spender, 0, 0);
+        contract.burn_from(owner.clone(), U128(30 * ONE));
+        assert_eq!(contract.get_allowance(owner.clone(), spender).0, 10 * ONE);
+        assert_eq!(contract.get_balance(owner.clone()).0, 70 * ONE);
+        assert_eq!(contract.get_total_supply().0, 70 * ONE);
+        assert_eq!(contract.get_burned_by(owner.clone()).0, 30 * ONE);
+        let burn = event_data("ft_burn");
+        assert_eq!(burn["owner_id"], owner.as_str());
+        assert_eq!(burn["amount"], (30 * ONE).to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "E20")]
+    fn burn_from_rejects_amounts_above_the_allowance() {
+        let (owner, spender) = (account("owner"), account("spender"));
+        let mut contract = setup_allowance(&owner, &spender);
+        set_context(&spender, 0, 0);
+        contract.burn_from(owner, U128(40 * ONE + 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "E4: Insufficient balance to burn")]
+    fn burn_from_rejects_amounts_above_the_balance() {
+        let (owner, spender) = (account("owner"), account("spender"));
+        let mut contract = setup_allowance(&owner, &spender);
+        contract.approve(spender.clone(), U128(200 * ONE));
+        set_context(&spender, 0, 0);
+        contract.burn_from(owner, U128(100 * ONE + 1));
+    }
+
+    #[test]
+    fn transfer_burns_round_up_and_balance_at_small_amounts() {
+        let mut contract = setup();
+        contract.set_burn_bps(100);
+        let (sender, receiver) = (account("sender"), account("receiver"));
+        mint(&mut contract, &sender, ONE);
+        for (amount, burned) in [(1, 1), (99, 1), (100, 1), (101, 2), (12_345, 124)] {
+            let sender_before = contract.get_balance(sender.clone()).0;
+            let receiver_before = contract.get_balance(receiver.clone()).0;
+            let supply_before = contract.get_total_supply().0;
+            set_context(&sender, 0, 0);
+            contract.tip(receiver.clone(), U128(amount));
+            assert_eq!(
+                sender_before - contract.get_balance(sender.clone()).0,
+                amount
+            );
+            assert_eq!(
+                contract.get_balance(receiver.clone()).0 - receiver_before,
+                amount - burned
+            );
+            assert_eq!(supply_before - contract.get_total_supply().0, burned);
+        }
+        assert_eq!(contract.get_total_burned().0, 129);
+    }
+
+    #[test]
+    #[should_panic(expected = "Transfer burn too high")]
+    fn transfer_burns_above_the_cap_are_rejected() {
+        let mut contract = setup();
+        contract.set_burn_bps(MAX_BURN_BPS + 1);
+    }
+
+    /// Gives `liquid` 100 liquid and 10 staked tokens, `staked` 20 liquid and
+    /// 200 staked tokens, and creates a proposal with the given weighting.
+    fn setup_weighting(use_staked_weight: bool) -> (Contract, u64) {
+        let mut contract = setup();
+        mint(&mut contract, &account("liquid"), 110 * ONE);
+        contract.stake(U128(10 * ONE));
+        mint(&mut contract, &account("staked"), 220 * ONE);
+        contract.stake(U128(200 * ONE));
+        set_context(&accounts(0), 0, 0);
+        contract.propose(
+            "Adopt the new logo".to_string(),
+            None,
+            None,
+            Some(use_staked_weight),
+            None,
+        );
+        (contract, 0)
+    }
+
+    #[test]
+    fn liquid_weighting_counts_liquid_balances() {
+        let (mut contract, id) = setup_weighting(false);
+        vote(&mut contract, &account("liquid"), id, true);
+        vote(&mut contract, &account("staked"), id, false);
+        let view = contract.get_proposal(id).unwrap();
+        assert_eq!(view.votes_for.0, 100 * ONE);
+        assert_eq!(view.votes_against.0, 20 * ONE);
+    }
+
+    #[test]
+    fn staked_weighting_counts_staked_balances() {
+        let (mut contract, id) = setup_weighting(true);
+        vote(&mut contract, &account("liquid"), id, true);
+        vote(&mut contract, &account("staked"), id, false);
+        let view = contract.get_proposal(id).unwrap();
+        assert!(view.use_staked_weight);
+        assert_eq!(view.votes_for.0, 10 * ONE);
+        assert_eq!(view.votes_against.0, 200 * ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "E43")]
+    fn staked_weighting_enforces_the_minimum_stake_to_vote() {
+        let (mut contract, id) = setup_weighting(true);
+        set_context(&accounts(0), 0, 0);
+        contract.set_min_stake_to_vote(U128(50 * ONE));
+        vote(&mut contract, &account("liquid"), id, true);
+    }
+
+    /// Deploys the contract with `oracle.near` as price oracle and has `minter`
+    /// attach 10 NEAR to `mint_with_oracle` at time 0, then sets up the oracle
+    /// callback 1 second later.
+    fn start_oracle_mint(minter: &AccountId) -> Contract {
+        let mut contract = setup();
+        contract.set_oracle(Some(account("oracle")));
+        set_context(minter, 10 * ONE, 0);
+        contract.mint_with_oracle();
+        let calls = function_calls();
+        assert_eq!(calls[0], (account("oracle"), "get_price".to_string()));
+        assert_eq!(
+            calls[1],
+            (account("memecoin"), "on_oracle_price".to_string())
+        );
+        set_context(&account("memecoin"), 0, SECOND);
+        contract
+    }
+
+    fn oracle_price(multiplier: u128, decimals: u8, timestamp: u64) -> OraclePrice {
+        OraclePrice {
+            multiplier: U128(multiplier),
+            decimals,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn oracle_mints_credit_the_priced_amount() {
+        let minter = account("minter");
+        let mut contract = start_oracle_mint(&minter);
+        let minted =
+            contract.on_oracle_price(minter.clone(), U128(10 * ONE), Ok(oracle_price(5, 1, 0)));
+        assert_eq!(minted.0, 5 * ONE);
+        assert_eq!(contract.get_balance(minter).0, 5 * ONE);
+        assert!(transfers().is_empty());
+    }
+
+    #[test]
+    fn oracle_mints_never_credit_more_than_the_deposit() {
+        let minter = account("minter");
+        let mut contract = start_oracle_mint(&minter);
+        let minted =
+            contract.on_oracle_price(minter.clone(), U128(10 * ONE), Ok(oracle_price(3, 0, 0)));
+        assert_eq!(minted.0, 10 * ONE);
+        assert_eq!(contract.get_balance(minter).0, 10 * ONE);
+    }
+
+    #[test]
+    fn failed_oracle_calls_refund_the_deposit() {
+        let minter = account("minter");
+        let mut contract = start_oracle_mint(&minter);
+        let minted =
+            contract.on_oracle_price(minter.clone(), U128(10 * ONE), Err(PromiseError::Failed));
+        assert_eq!(minted.0, 0);
+        assert_eq!(transfers(), vec![(minter.clone(), 10 * ONE)]);
+        assert_eq!(contract.get_balance(minter).0, 0);
+    }
+
+    #[test]
+    fn zero_oracle_prices_refund_the_deposit() {
+        let minter = account("minter");
+        let mut contract = start_oracle_mint(&minter);
+        let minted =
+            contract.on_oracle_price(minter.clone(), U128(10 * ONE), Ok(oracle_price(0, 0, 0)));
+        assert_eq!(minted.0, 0);
+        assert_eq!(transfers(), vec![(minter, 10 * ONE)]);
+    }
+
+    #[test]
+    fn stale_oracle_prices_refund_the_deposit() {
+        let minter = account("minter");
+        let mut contract = start_oracle_mint(&minter);
+        set_context(&account("memecoin"), 0, MAX_ORACLE_PRICE_AGE_NS + 1);
+        let minted =
+            contract.on_oracle_price(minter.clone(), U128(10 * ONE), Ok(oracle_price(5, 1, 0)));
+        assert_eq!(minted.0, 0);
+        assert_eq!(transfers(), vec![(minter, 10 * ONE)]);
+    }
+
+    /// Gives the treasury (the admin) 100 tokens and `voter.near` 50, then has
+    /// the voter back a proposal to burn 10 treasury tokens. Returns its ID.
+    fn setup_treasury_burn(contract: &mut Contract) -> u64 {
+        mint(contract, &accounts(0), 100 * ONE);
+        mint(contract, &account("voter"), 50 * ONE);
+        set_context(&accounts(0), 0, 0);
+        contract.propose(
+            "Burn part of the treasury".to_string(),
+            None,
+            None,
+            None,
+            Some(ProposalAction::BurnTreasury {
+                amount: U128(10 * ONE),
+            }),
+        );
+        let id = contract.next_proposal_id - 1;
+        vote(contract, &account("voter"), id, true);
+        id
+    }
+
+    /// Moves past the veto window of proposal `id` as the admin.
+    fn end_veto_window(contract: &Contract, id: u64) {
+        let deadline = contract.get_proposal(id).unwrap().deadline;
+        set_context(&accounts(0), 0, deadline + VETO_WINDOW_SECONDS * SECOND);
+    }
+
+    #[test]
+    fn finalizing_twice_panics_and_runs_the_action_once() {
+        let mut contract = setup();
+        let admin = accounts(0);
+        setup_treasury_burn(&mut contract);
+        end_veto_window(&contract, 0);
+        contract.finalize_proposal(0);
+        let view = contract.get_proposal(0).unwrap();
+        assert!(view.finalized && view.passed);
+        assert_eq!(contract.get_total_supply().0, 140 * ONE);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            contract.finalize_proposal(0)
+        }));
+        assert!(result.is_err());
+        assert_eq!(contract.get_total_supply().0, 140 * ONE);
+        assert_eq!(contract.get_balance(admin).0, 90 * ONE);
+        assert_eq!(contract.get_active_proposal_count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "E37")]
+    fn finalizing_twice_is_rejected() {
+        let mut contract = setup();
+        mint(&mut contract, &account("voter"), 50 * ONE);
+        let id = propose(&mut contract, false);
+        vote(&mut contract, &account("voter"), id, true);
+        let deadline = contract.get_proposal(id).unwrap().deadline;
+        set_context(&accounts(0), 0, deadline);
+        contract.finalize_proposal(id);
+        contract.finalize_proposal(id);
+    }
+
+    /// Ed25519 key for signing permits, derived from `seed`.
+    fn signing_key(seed: u8) -> ed25519_dalek::SigningKey {
+        ed25519_dalek::SigningKey::from_bytes(&[seed; 32])
+    }
+
+    /// Registers `key` as `owner`'s signing key.
+    fn register_key(contract: &mut Contract, owner: &AccountId, key: &ed25519_dalek::SigningKey) {
+        set_context(owner, 0, 0);
+        contract.register_signing_key(key.verifying_key().to_bytes().to_vec());
+    }
+
+    /// Signs a permit for `spender` to spend `amount` of `owner`'s tokens.
+    fn sign_permit(
+        key: &ed25519_dalek::SigningKey,
+        owner: &AccountId,
+        spender: &AccountId,
+        amount: u128,
+        nonce: u64,
+        deadline: u64,
+    ) -> Vec<u8> {
+        use ed25519_dalek::Signer;
+        let message = format!(
+            "memecoin.near:permit:{}:{}:{}:{}:{}",
+            owner, spender, amount, nonce, deadline
+        );
+        key.sign(message.as_bytes()).to_bytes().to_vec()
+    }
+
+    /// Registers `signing_key(1)` for `owner.near`, then relays at time 1 second
+    /// a permit for `spender.near` signed with `signing_key(signer)`.
+    fn relay_permit(contract: &mut Contract, signer: u8, deadline: u64) {
+        let (owner, spender) = (account("owner"), account("spender"));
+        let key = signing_key(1);
+        register_key(contract, &owner, &key);
+        let nonce = contract.get_permit_nonce(owner.clone());
+        let signature = sign_permit(
+            &signing_key(signer),
+            &owner,
+            &spender,
+            40 * ONE,
+            nonce,
+            deadline,
+        );
+        set_context(&account("relayer"), 0, SECOND);
+        contract.permit(
+            owner,
+            spender,
+            U128(40 * ONE),
+            deadline,
+            signature,
+            key.verifying_key().to_bytes().to_vec(),
+        );
+    }
+
+    #[test]
+    fn valid_permits_set_the_allowance() {
+        let mut contract = setup();
+        relay_permit(&mut contract, 1, SECOND);
+        assert_eq!(
+            contract
+                .get_allowance(account("owner"), account("spender"))
+                .0,
+            40 * ONE
+        );
+        assert_eq!(contract.get_permit_nonce(account("owner")), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "E21")]
+    fn expired_permits_are_rejected() {
+        let mut contract = setup();
+        relay_permit(&mut contract, 1, SECOND - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "E22")]
+    fn permits_signed_by_another_key_are_rejected() {
+        let mut contract = setup();
+        relay_permit(&mut contract, 2, SECOND);
+    }
+
+    #[test]
+    #[should_panic(expected = "E22")]
+    fn replayed_permits_are_rejected() {
+        let mut contract = setup();
+        let (owner, spender) = (account("owner"), account("spender"));
+        let key = signing_key(1);
+        register_key(&mut contract, &owner, &key);
+        let signature = sign_permit(&key, &owner, &spender, 40 * ONE, 0, SECOND);
+        let public_key = key.verifying_key().to_bytes().to_vec();
+        set_context(&account("relayer"), 0, SECOND);
+        for _ in 0..2 {
+            contract.permit(
+                owner.clone(),
+                spender.clone(),
+                U128(40 * ONE),
+                SECOND,
+                signature.clone(),
+                public_key.clone(),
+            );
+        }
+    }
+
+    #[test]
+    fn referral_bonuses_stop_at_the_cap_while_minting_continues() {
+        let (mut contract, referrer) = mint_referred(false);
+        set_context(&accounts(0), 0, 0);
+        contract.set_max_referral_supply(U128(3 * ONE / 2));
+        assert_eq!(contract.get_max_referral_supply().0, 3 * ONE / 2);
+        let minter = account("minter");
+        mint(&mut contract, &minter, 100 * ONE);
+        assert_eq!(contract.get_balance(referrer.clone()).0, 3 * ONE / 2);
+        mint(&mut contract, &minter, 100 * ONE);
+        assert_eq!(
+            event_data("referral_bonus_skipped")["reason"],
+            "cap reached"
+        );
+        assert_eq!(contract.get_balance(referrer).0, 3 * ONE / 2);
+        assert_eq!(contract.get_balance(minter).0, 300 * ONE);
+        assert_eq!(contract.get_total_referral_minted().0, 3 * ONE / 2);
+        assert_eq!(contract.get_total_supply().0, 300 * ONE + 3 * ONE / 2);
+    }
+
+    #[test]
+    fn admins_can_reassign_referrals() {
+        let mut contract = setup();
+        set_context(&account("ann"), 0, 0);
+        contract.register_referral(account("ben"));
+        set_context(&accounts(0), 0, 0);
+        contract.admin_set_referral(account("ann"), Some(account("cat")));
+        assert_eq!(contract.get_referrer(account("ann")), Some(account("cat")));
+        assert_eq!(contract.get_referral_count(account("ben")), 0);
+        assert_eq!(contract.get_referral_count(account("cat")), 1);
+        let event = event_data("referral_reassigned");
+        assert_eq!(event["admin_id"], accounts(0).as_str());
+        assert_eq!(event["old_referrer_id"], "ben.near");
+        contract.admin_set_referral(account("ann"), None);
+        assert_eq!(contract.get_referrer(account("ann")), None);
+        assert_eq!(contract.get_referral_count(account("cat")), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Referral cycle detected")]
+    fn referral_reassignments_creating_a_cycle_are_rejected() {
+        let mut contract = setup();
+        set_context(&account("ann"), 0, 0);
+        contract.register_referral(account("ben"));
+        set_context(&account("ben"), 0, 0);
+        contract.register_referral(account("cat"));
+        set_context(&accounts(0), 0, 0);
+        contract.admin_set_referral(account("cat"), Some(account("ann")));
+    }
+
+    #[test]
+    fn minting_and_staking_work_while_transfers_are_disabled() {
+        let mut contract = setup();
+        contract.set_transfers_enabled(false);
+        assert!(!contract.are_transfers_enabled());
+        let holder = account("holder");
+        mint(&mut contract, &holder, 100 * ONE);
+        contract.stake(U128(40 * ONE));
+        let summary = contract.get_account_summary(holder);
+        assert_eq!(summary.balance.0, 60 * ONE);
+        assert_eq!(summary.staked.0, 40 * ONE);
+        // The admin can still seed liquidity.
+        mint(&mut contract, &accounts(0), 100 * ONE);
+        contract.tip(account("pool"), U128(10 * ONE));
+        assert_eq!(contract.get_balance(account("pool")).0, 10 * ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "E12")]
+    fn tips_are_rejected_while_transfers_are_disabled() {
+        let mut contract = setup();
+        contract.set_transfers_enabled(false);
+        mint(&mut contract, &account("holder"), 100 * ONE);
+        contract.tip(account("receiver"), U128(ONE));
+    }
+
+    #[test]
+    fn claim_rewards_to_credits_the_beneficiary() {
+        let mut contract = setup();
+        let (staker, beneficiary) = (account("staker"), account("payout"));
+        mint_and_stake(&mut contract, &staker, 1_000 * ONE);
+        set_context(&staker, 0, YEAR_NS as u64);
+        contract.claim_rewards_to(beneficiary.clone());
+        assert_eq!(contract.get_balance(beneficiary.clone()).0, 50 * ONE);
+        assert_eq!(contract.get_balance(staker.clone()).0, 0);
+        assert_eq!(contract.get_claimable_rewards(staker.clone()).0, 0);
+        let event = event_data("claim_rewards_to");
+        assert_eq!(event["staker_id"], staker.as_str());
+        assert_eq!(event["beneficiary_id"], beneficiary.as_str());
+        assert_eq!(event["amount"], (50 * ONE).to_string());
+    }
+
+    /// Creates a proposal under a 50% quorum while `big` holds 60 and `small`
+    /// 40 tokens, then mints 1000 more tokens to `late`.
+    fn setup_percentage_quorum(quadratic: bool) -> (Contract, u64) {
+        let mut contract = setup();
+        contract.set_quorum_mode(QuorumMode::Percentage { pct: 50 });
+        mint(&mut contract, &account("big"), 60 * ONE);
+        mint(&mut contract, &account("small"), 40 * ONE);
+        let id = propose(&mut contract, quadratic);
+        mint(&mut contract, &account("late"), 1_000 * ONE);
+        (contract, id)
+    }
+
+    /// Finalizes proposal `id` once voting has ended and returns its view.
+    fn finalize(contract: &mut Contract, id: u64) -> ProposalView {
+        let deadline = contract.get_proposal(id).unwrap().deadline;
+        set_context(&accounts(0), 0, deadline);
+        contract.finalize_proposal(id);
+        contract.get_proposal(id).unwrap()
+    }
+
+    #[test]
+    fn percentage_quorum_uses_the_supply_snapshot() {
+        let (mut contract, id) = setup_percentage_quorum(false);
+        assert_eq!(contract.get_circulating_supply().0, 1_100 * ONE);
+        let view = contract.get_proposal(id).unwrap();
+        assert_eq!(view.supply_snapshot.0, 100 * ONE);
+        assert_eq!(view.quorum.0, 50 * ONE);
+        vote(&mut contract, &account("big"), id, true);
+        let view = finalize(&mut contract, id);
+        assert!(view.passed);
+        assert_eq!(view.turnout_bps, Some(6_000));
+    }
+
+    #[test]
+    fn turnout_below_the_snapshot_quorum_fails() {
+        let (mut contract, id) = setup_percentage_quorum(false);
+        vote(&mut contract, &account("small"), id, true);
+        assert!(!finalize(&mut contract, id).passed);
+    }
+
+    #[test]
+    fn quorum_counts_tokens_on_quadratic_proposals() {
+        let (mut contract, id) = setup_percentage_quorum(true);
+        vote(&mut contract, &account("big"), id, true);
+        let view = finalize(&mut contract, id);
+        assert_eq!(view.votes_for.0, isqrt(60 * ONE));
+        assert_eq!(view.raw_turnout.0, 60 * ONE);
+        assert!(view.passed);
+    }
+
+    /// Mints an NFT for `minter` with ample deposit.
+    fn mint_nft(contract: &mut Contract, minter: &AccountId) {
+        set_context(minter, ONE, 0);
+        contract.nft_mint("{}".to_string(), None, None);
+    }
+
+    fn token_ids(tokens: Vec<TokenView>) -> Vec<u64> {
+        tokens.into_iter().map(|token| token.token_id).collect()
+    }
+
+    #[test]
+    fn nft_enumeration_paginates_globally_and_per_owner() {
+        let mut contract = setup();
+        let (ann, ben) = (account("ann"), account("ben"));
+        for minter in [&ann, &ben, &ann, &ben, &ann] {
+            mint_nft(&mut contract, minter);
+        }
+        assert_eq!(contract.nft_total_supply().0, 5);
+        assert_eq!(
+            token_ids(contract.nft_tokens(Some(1), Some(3))),
+            vec![1, 2, 3]
+        );
+        assert_eq!(token_ids(contract.nft_tokens(Some(4), None)), vec![4]);
+        assert_eq!(
+            token_ids(contract.nft_tokens_for_owner(ann.clone(), Some(1), Some(2))),
+            vec![2, 4]
+        );
+        assert_eq!(
+            token_ids(contract.nft_tokens_for_owner(ben.clone(), None, Some(1))),
+            vec![1]
+        );
+
+        set_context(&ann, 1, 0);
+        contract.nft_transfer(ben.clone(), 2, None);
+        let mut ann_tokens = token_ids(contract.nft_tokens_for_owner(ann.clone(), None, None));
+        ann_tokens.sort();
+        assert_eq!(ann_tokens, vec![0, 4]);
+        let mut ben_tokens = token_ids(contract.nft_tokens_for_owner(ben.clone(), None, None));
+        ben_tokens.sort();
+        assert_eq!(ben_tokens, vec![1, 2, 3]);
+        assert_eq!(contract.nft_supply_for_owner(ann).0, 2);
+        assert_eq!(contract.nft_supply_for_owner(ben).0, 3);
+    }
+
+    #[test]
+    fn nft_holders_earn_boosted_rewards() {
+        let mut contract = setup();
+        contract.set_nft_boost(2_000);
+        let (holder, other) = (account("holder"), account("other"));
+        mint_nft(&mut contract, &holder);
+        mint_and_stake(&mut contract, &holder, 1_000 * ONE);
+        mint_and_stake(&mut contract, &other, 1_000 * ONE);
+        assert_eq!(contract.get_apr(Some(holder.clone())), 600);
+        assert_eq!(contract.get_apr(Some(other.clone())), 500);
+        assert_eq!(contract.get_apr(None), 500);
+        set_context(&holder, 0, YEAR_NS as u64);
+        assert_eq!(contract.get_claimable_rewards(holder.clone()).0, 60 * ONE);
+        assert_eq!(contract.get_claimable_rewards(other).0, 50 * ONE);
+        contract.claim_rewards(None);
+        assert_eq!(contract.get_balance(holder).0, 60 * ONE);
+    }
+
+    #[test]
+    fn merging_combines_overlapping_positions() {
+        let mut contract = setup();
+        let (old, new) = (account("old"), account("new"));
+        set_context(&old, 0, 0);
+        contract.register_referral(account("referrer"));
+        for (holder, staked, tipped) in [(&old, 50, 10), (&new, 20, 5)] {
+            mint_and_stake(&mut contract, holder, staked * ONE);
+            mint(&mut contract, holder, 100 * ONE);
+            contract.tip(account("receiver"), U128(tipped * ONE));
+        }
+        set_context(&new, 0, 0);
+        contract.register_referral(old.clone());
+        // old, new, the tip receiver and old's referrer.
+        assert_eq!(contract.get_holder_count(), 4);
+        assert_eq!(contract.get_staking_stats().staker_count, 2);
+
+        set_context(&accounts(0), 0, 0);
+        let id = contract.merge_accounts(old.clone(), new.clone());
+        let merge_at = DEFAULT_TIMELOCK_DELAY_SECONDS * SECOND;
+        set_context(&accounts(0), 0, merge_at);
+        let rewards = contract.get_claimable_rewards(old.clone()).0
+            + contract.get_claimable_rewards(new.clone()).0;
+        contract.execute_action(id);
+
+        let summary = contract.get_account_summary(new.clone());
+        assert_eq!(summary.balance.0, 185 * ONE);
+        assert_eq!(summary.staked.0, 70 * ONE);
+        assert_eq!(summary.pending_rewards.0, rewards);
+        assert_eq!(summary.tip_total.0, 15 * ONE);
+        assert_eq!(summary.referrer, Some(account("referrer")));
+        assert_eq!(contract.get_account_summary(old.clone()).staked.0, 0);
+        assert_eq!(contract.get_balance(old.clone()).0, 0);
+        assert_eq!(contract.get_referral_count(account("referrer")), 1);
+        assert_eq!(contract.get_referral_count(old), 0);
+        assert_eq!(contract.get_holder_count(), 3);
+        assert_eq!(contract.get_staking_stats().staker_count, 1);
+        assert_eq!(
+            contract.get_tip_totals(0, 10),
+            vec![(new.clone(), U128(15 * ONE))]
+        );
+        assert_eq!(contract.get_top_tipper(), Some(new));
+    }
+
+    /// Enables staker proposals at 100 staked tokens, then has `proposer.near`
+    /// stake `staked` tokens and create one.
+    fn propose_as_staker(staked: u128) -> Contract {
+        let mut contract = setup();
+        contract.set_min_stake_to_propose(U128(100 * ONE));
+        assert_eq!(contract.get_min_stake_to_propose().0, 100 * ONE);
+        mint_and_stake(&mut contract, &account("proposer"), staked);
+        contract.propose_staked("Adopt the new logo".to_string(), None, None, None, None);
+        contract
+    }
+
+    #[test]
+    fn stakers_at_the_threshold_can_propose() {
+        let contract = propose_as_staker(100 * ONE);
+        assert_eq!(
+            contract.get_proposal(0).unwrap().proposer,
+            account("proposer")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "E44")]
+    fn stakers_below_the_threshold_cannot_propose() {
+        propose_as_staker(100 * ONE - 1);
+    }
+
+    #[test]
+    fn known_failures_panic_with_their_error_codes() {
+        let holder = account("holder");
+        type Call = fn(&mut Contract);
+        let cases: [(&str, Call); 6] = [
+            (errors::DEPOSIT_TOO_LOW, |contract| {
+                set_context(&account("holder"), 1, 0);
+                contract.mint();
+            }),
+            (errors::INSUFFICIENT_BALANCE, |contract| {
+                contract.tip(account("receiver"), U128(200 * ONE));
+            }),
+            (errors::INSUFFICIENT_BALANCE_TO_STAKE, |contract| {
+                contract.stake(U128(200 * ONE));
+            }),
+            (errors::INSUFFICIENT_STAKED, |contract| {
+                contract.unstake(U128(11 * ONE));
+            }),
+            (errors::PROPOSAL_NOT_FOUND, |contract| {
+                contract.vote(7, true)
+            }),
+            (errors::NO_PENDING_REFUND, |contract| {
+                contract.claim_mint_refund();
+            }),
+        ];
+        for (code, call) in cases {
+            let mut contract = setup_holder(&holder);
+            assert_eq!(panic_message(|| call(&mut contract)), code);
+        }
+    }
+
+    /// Gives `staker` 100 tokens and locks them for 60 seconds at time 0.
+    /// Returns the receipt's token ID.
+    fn setup_stake_lock(contract: &mut Contract, staker: &AccountId) -> u64 {
+        mint(contract, staker, 100 * ONE);
+        contract.stake_locked(U128(100 * ONE), 60);
+        contract.get_stake_lock(staker.clone()).unwrap().receipt_id
+    }
+
+    #[test]
+    fn locked_stakes_get_a_soulbound_receipt_burned_on_full_unstake() {
+        let mut contract = setup();
+        let staker = account("staker");
+        let receipt_id = setup_stake_lock(&mut contract, &staker);
+        let receipt = contract.nft_token(receipt_id).unwrap();
+        assert_eq!(receipt.owner_id, staker);
+        assert!(receipt.soulbound);
+        assert_eq!(contract.get_stake_receipt_count(), 1);
+        assert_eq!(contract.nft_total_supply().0, 0);
+
+        set_context(&staker, 0, 60 * SECOND);
+        contract.unstake(U128(100 * ONE));
+        assert!(contract.nft_token(receipt_id).is_none());
+        assert!(contract.get_stake_lock(staker.clone()).is_none());
+        assert_eq!(contract.get_stake_receipt_count(), 0);
+        assert_eq!(contract.nft_supply_for_owner(staker).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "E59")]
+    fn stake_receipts_cannot_be_transferred() {
+        let mut contract = setup();
+        let staker = account("staker");
+        let receipt_id = setup_stake_lock(&mut contract, &staker);
+        set_context(&staker, 1, 0);
+        contract.nft_transfer(account("buyer"), receipt_id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "E58")]
+    fn locked_stakes_cannot_be_unstaked_early() {
+        let mut contract = setup();
+        let staker = account("staker");
+        setup_stake_lock(&mut contract, &staker);
+        set_context(&staker, 0, 60 * SECOND - 1);
+        contract.unstake(U128(ONE));
+    }
+
+    #[test]
+    fn airdrops_log_a_single_event_for_all_recipients() {
+        let mut contract = setup();
+        let sender = account("sender");
+        mint(&mut contract, &sender, 100 * ONE);
+        let receivers = vec![account("ann"), account("ben"), account("cat")];
+        let amounts = vec![U128(ONE), U128(2 * ONE), U128(3 * ONE)];
+        set_context(&sender, 0, 0);
+        contract.airdrop(receivers.clone(), amounts.clone());
+        let events = events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["event"], "ft_transfer");
+        let data = events[0]["data"].as_array().unwrap();
+        assert_eq!(data.len(), 3);
+        for ((entry, receiver), amount) in data.iter().zip(&receivers).zip(&amounts) {
+            assert_eq!(entry["old_owner_id"], sender.as_str());
+            assert_eq!(entry["new_owner_id"], receiver.as_str());
+            assert_eq!(entry["amount"], amount.0.to_string());
+            assert_eq!(contract.get_balance(receiver.clone()), *amount);
+        }
+        assert_eq!(contract.get_balance(sender).0, 94 * ONE);
+    }
+
+    #[test]
+    fn airdrops_burn_each_entry_like_a_tip() {
+        let mut contract = setup();
+        contract.set_burn_bps(100);
+        let sender = account("sender");
+        mint(&mut contract, &sender, 100 * ONE);
+        contract.airdrop(
+            vec![account("ann"), account("ben")],
+            vec![U128(1), U128(101)],
+        );
+        assert_eq!(contract.get_balance(account("ann")).0, 0);
+        assert_eq!(contract.get_balance(account("ben")).0, 99);
+        assert_eq!(contract.get_total_burned().0, 3);
+        assert_eq!(event_data("ft_burn")["amount"], "3");
+        assert_eq!(contract.get_total_supply().0, 100 * ONE - 3);
+    }
+
+    /// Deploys the contract with a 100 token max supply, of which 60 are minted.
+    fn setup_supply_cap() -> Contract {
+        let mut contract = setup();
+        contract.set_max_supply(U128(100 * ONE));
+        mint(&mut contract, &account("early"), 60 * ONE);
+        contract
+    }
+
+    #[test]
+    fn mints_exactly_at_the_cap_are_fully_credited() {
+        let mut contract = setup_supply_cap();
+        let minter = account("minter");
+        mint(&mut contract, &minter, 40 * ONE);
+        assert_eq!(contract.get_balance(minter).0, 40 * ONE);
+        assert_eq!(contract.get_total_supply().0, 100 * ONE);
+        assert!(transfers().is_empty());
+    }
+
+    #[test]
+    fn mints_partly_over_the_cap_refund_the_excess() {
+        let mut contract = setup_supply_cap();
+        let minter = account("minter");
+        mint(&mut contract, &minter, 100 * ONE);
+        assert_eq!(contract.get_balance(minter.clone()).0, 40 * ONE);
+        assert_eq!(contract.get_total_supply().0, 100 * ONE);
+        assert_eq!(transfers(), vec![(minter, 60 * ONE)]);
+        let event = event_data("mint_capped");
+        assert_eq!(event["credited"], (40 * ONE).to_string());
+        assert_eq!(event["refunded"], (60 * ONE).to_string());
+    }
+
+    #[test]
+    fn mints_fully_over_the_cap_refund_everything() {
+        let mut contract = setup_supply_cap();
+        mint(&mut contract, &account("filler"), 40 * ONE);
+        let minter = account("minter");
+        mint(&mut contract, &minter, 10 * ONE);
+        assert!(!contract.is_registered(minter.clone()));
+        assert_eq!(contract.get_holder_count(), 2);
+        assert_eq!(contract.get_total_supply().0, 100 * ONE);
+        assert_eq!(transfers(), vec![(minter, 10 * ONE)]);
+    }
+
+    #[test]
+    fn undelivered_mint_refunds_can_be_claimed() {
+        let mut contract = setup_supply_cap();
+        let minter = account("minter");
+        set_context(&account("memecoin"), 0, 0);
+        contract.on_mint_refund_resolved(minter.clone(), U128(60 * ONE), Err(PromiseError::Failed));
+        assert_eq!(contract.get_pending_mint_refund(minter.clone()).0, 60 * ONE);
+        set_context(&minter, 0, 0);
+        contract.claim_mint_refund();
+        assert_eq!(transfers(), vec![(minter.clone(), 60 * ONE)]);
+        assert_eq!(contract.get_pending_mint_refund(minter).0, 0);
+    }
+
+    /// Runs queued action `id` as the admin once the default timelock expired.
+    fn execute_after_timelock(contract: &mut Contract, id: u64) {
+        set_context(&accounts(0), 0, DEFAULT_TIMELOCK_DELAY_SECONDS * SECOND);
+        contract.execute_action(id);
+    }
+
+    #[test]
+    fn the_admin_reserve_is_excluded_from_circulating_supply() {
+        let mut contract = setup();
+        let id = contract.set_treasury(account("treasury"));
+        execute_after_timelock(&mut contract, id);
+        let admin = accounts(0);
+        mint(&mut contract, &admin, 100 * ONE);
+        mint(&mut contract, &account("holder"), 50 * ONE);
+        assert_eq!(contract.get_circulating_supply().0, 150 * ONE);
+
+        set_context(&admin, 0, 0);
+        contract.set_admin_reserve(U128(40 * ONE));
+        assert_eq!(contract.get_admin_reserve().0, 40 * ONE);
+        assert_eq!(contract.get_balance(admin.clone()).0, 60 * ONE);
+        assert_eq!(contract.get_circulating_supply().0, 110 * ONE);
+        assert_eq!(contract.get_total_supply().0, 150 * ONE);
+
+        let id = contract.release_reserve(U128(15 * ONE));
+        assert_eq!(contract.get_circulating_supply().0, 110 * ONE);
+        execute_after_timelock(&mut contract, id);
+        assert_eq!(contract.get_admin_reserve().0, 25 * ONE);
+        assert_eq!(contract.get_balance(admin).0, 75 * ONE);
+        assert_eq!(contract.get_circulating_supply().0, 125 * ONE);
+    }
+
+    #[test]
+    fn vetoed_proposals_keep_their_tallies_but_skip_the_action() {
+        let mut contract = setup();
+        let id = setup_treasury_burn(&mut contract);
+        set_context(&accounts(0), 0, 2 * SECOND);
+        contract.veto_proposal(id, "Treasury is needed for listing".to_string());
+        end_veto_window(&contract, id);
+        contract.finalize_proposal(id);
+        let view = contract.get_proposal(id).unwrap();
+        assert!(view.finalized && view.passed && view.vetoed);
+        assert_eq!(view.votes_for.0, 50 * ONE);
+        assert_eq!(
+            view.veto_reason.as_deref(),
+            Some("Treasury is needed for listing")
+        );
+        assert_eq!(contract.get_total_supply().0, 150 * ONE);
+        assert_eq!(contract.get_balance(accounts(0)).0, 100 * ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Veto window has ended")]
+    fn vetoes_after_the_veto_window_are_rejected() {
+        let mut contract = setup();
+        let id = setup_treasury_burn(&mut contract);
+        end_veto_window(&contract, id);
+        contract.veto_proposal(id, "Treasury is needed for listing".to_string());
+    }
+
+    /// Enables redemption at 0.9 NEAR per token and gives `holder` 10 tokens.
+    fn setup_redeem(holder: &AccountId) -> Contract {
+        let mut contract = setup();
+        contract.set_redeem_rate(9_000);
+        mint(&mut contract, holder, 10 * ONE);
+        contract
+    }
+
+    #[test]
+    fn redeems_paying_at_least_the_minimum_go_through() {
+        let holder = account("holder");
+        let mut contract = setup_redeem(&holder);
+        contract.redeem(U128(10 * ONE), U128(9 * ONE));
+        assert_eq!(transfers(), vec![(holder.clone(), 9 * ONE)]);
+        assert_eq!(contract.get_balance(holder).0, 0);
+        assert_eq!(contract.get_total_supply().0, 0);
+    }
+
+    #[test]
+    fn redeems_paying_below_the_minimum_change_nothing() {
+        let holder = account("holder");
+        let mut contract = setup_redeem(&holder);
+        let message = panic_message(|| contract.redeem(U128(10 * ONE), U128(9 * ONE + 1)));
+        assert_eq!(message, errors::REDEMPTION_BELOW_MINIMUM);
+        assert!(transfers().is_empty());
+        assert_eq!(contract.get_balance(holder).0, 10 * ONE);
+        assert_eq!(contract.get_total_supply().0, 10 * ONE);
+        assert_eq!(contract.get_total_burned().0, 0);
+    }
+
+    #[test]
+    fn proposal_enumeration_skips_cancelled_proposals() {
+        let mut contract = setup();
+        for step in [
+            "create", "create", "create", "cancel 1", "create", "cancel 0", "create",
+        ] {
+            match step.strip_prefix("cancel ") {
+                Some(id) => contract.cancel_proposal(id.parse().unwrap()),
+                None => {
+                    propose(&mut contract, false);
+                }
+            }
+        }
+        let mut ids: Vec<u64> = contract
+            .get_proposals(None, None)
+            .into_iter()
+            .map(|proposal| proposal.id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec![2, 3, 4]);
+        let mut active = contract.get_active_proposal_ids(None, None);
+        active.sort();
+        assert_eq!(active, vec![2, 3, 4]);
+        assert_eq!(contract.get_active_proposal_ids(Some(1), Some(1)).len(), 1);
+        assert_eq!(contract.get_proposals(Some(2), Some(5)).len(), 1);
+
+        let deadline = contract.get_proposal(2).unwrap().deadline;
+        set_context(&accounts(0), 0, deadline);
+        assert!(contract.get_active_proposal_ids(None, None).is_empty());
+        contract.finalize_proposal(2);
+        assert_eq!(contract.get_proposals(None, None).len(), 3);
+    }
+
+    /// Stakes 1000 tokens of `staker` under a 10% emission starting at
+    /// `start` and halving yearly.
+    fn setup_halving(staker: &AccountId, start: u64) -> Contract {
+        let mut contract = setup();
+        contract.set_emission_schedule(Some(EmissionSchedule {
+            start,
+            initial_rate_bps: 1_000,
+            halving_interval_seconds: 365 * 24 * 60 * 60,
+        }));
+        mint_and_stake(&mut contract, staker, 1_000 * ONE);
+        contract
+    }
+
+    /// Claims `staker`'s rewards at `timestamp` and returns the amount claimed.
+    fn claim_at(contract: &mut Contract, staker: &AccountId, timestamp: u64) -> u128 {
+        let before = contract.get_balance(staker.clone()).0;
+        set_context(staker, 0, timestamp);
+        contract.claim_rewards(None);
+        contract.get_balance(staker.clone()).0 - before
+    }
+
+    #[test]
+    fn halving_emission_splits_accrual_at_halvings() {
+        let staker = account("staker");
+        let mut contract = setup_halving(&staker, 0);
+        let year = YEAR_NS as u64;
+        // Wholly before the first halving.
+        assert_eq!(claim_at(&mut contract, &staker, year / 2), 50 * ONE);
+        // Across it: half a year at 10%, then half a year at 5%.
+        assert_eq!(claim_at(&mut contract, &staker, 3 * year / 2), 75 * ONE);
+        // Wholly after it.
+        assert_eq!(claim_at(&mut contract, &staker, 2 * year), 25 * ONE);
+        assert_eq!(claim_at(&mut contract, &staker, 3 * year), 25 * ONE);
+        assert_eq!(contract.get_apr(None), 125);
+    }
+
+    #[test]
+    fn halving_emission_accrues_nothing_before_its_start() {
+        let staker = account("staker");
+        let year = YEAR_NS as u64;
+        let mut contract = setup_halving(&staker, year);
+        set_context(&staker, 0, year);
+        assert_eq!(contract.get_claimable_rewards(staker.clone()).0, 0);
+        assert_eq!(claim_at(&mut contract, &staker, 3 * year / 2), 50 * ONE);
+    }
+
+    /// Sets up a call by the admin at `timestamp` while the contract holds 5
+    /// NEAR beyond its storage costs and the backing of its 100 tokens.
+    fn set_surplus_context(timestamp: u64) {
+        let storage_usage = 10_000;
+        let storage_cost = env::storage_byte_cost().as_yoctonear() * storage_usage as u128;
+        let mut context = context(&accounts(0), 0, timestamp);
+        context
+            .storage_usage(storage_usage)
+            .account_balance(NearToken::from_yoctonear(storage_cost + 105 * ONE));
+        testing_env!(context.build());
+    }
+
+    #[test]
+    fn unaccounted_near_can_be_recovered_to_the_treasury() {
+        let mut contract = setup();
+        mint(&mut contract, &account("holder"), 100 * ONE);
+        set_surplus_context(0);
+        assert_eq!(contract.get_unaccounted_near().0, 5 * ONE);
+        let id = contract.recover_unaccounted_near(U128(5 * ONE));
+        set_surplus_context(DEFAULT_TIMELOCK_DELAY_SECONDS * SECOND);
+        contract.execute_action(id);
+        assert_eq!(transfers(), vec![(accounts(0), 5 * ONE)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount exceeds the unaccounted NEAR")]
+    fn recovering_more_than_the_surplus_is_rejected() {
+        let mut contract = setup();
+        mint(&mut contract, &account("holder"), 100 * ONE);
+        set_surplus_context(0);
+        contract.recover_unaccounted_near(U128(5 * ONE + 1));
+    }
+
+    #[test]
+    fn claimable_rewards_view_matches_the_claim_and_writes_nothing() {
+        let staker = account("staker");
+        let year = YEAR_NS as u64;
+        let mut contract = setup_halving(&staker, 0);
+        set_context(&accounts(0), 0, 0);
+        contract.set_nft_boost(2_000);
+        mint_nft(&mut contract, &staker);
+        set_context(&staker, 0, 3 * year / 2);
+        let storage_usage = env::storage_usage();
+        let claimable = contract.get_claimable_rewards(staker.clone()).0;
+        assert_eq!(contract.get_claimable_rewards(staker.clone()).0, claimable);
+        assert_eq!(env::storage_usage(), storage_usage);
+        assert!(get_logs().is_empty());
+        assert_eq!(claimable, 150 * ONE);
+        assert_eq!(claim_at(&mut contract, &staker, 3 * year / 2), claimable);
+    }
+
+    #[test]
+    fn proposal_creation_resumes_after_one_is_finalized_at_the_cap() {
+        let mut contract = setup();
+        contract.set_max_active_proposals(2);
+        assert_eq!(contract.get_max_active_proposals(), 2);
+        propose(&mut contract, false);
+        propose(&mut contract, false);
+        assert_eq!(contract.get_active_proposal_count(), 2);
+        let message = panic_message(|| propose(&mut contract, false));
+        assert_eq!(message, errors::TOO_MANY_ACTIVE_PROPOSALS);
+        finalize(&mut contract, 0);
+        assert_eq!(contract.get_active_proposal_count(), 1);
+        propose(&mut contract, false);
+        assert_eq!(contract.get_active_proposal_count(), 2);
+    }
+
+    #[test]
+    fn restaking_settles_rewards_on_the_original_principal() {
+        let mut contract = setup();
+        let staker = account("staker");
+        mint_and_stake(&mut contract, &staker, 1_000 * ONE);
+        mint(&mut contract, &staker, 1_000 * ONE);
+        let year = YEAR_NS as u64;
+        set_context(&staker, 0, year);
+        contract.stake(U128(1_000 * ONE));
+        assert_eq!(contract.get_claimable_rewards(staker.clone()).0, 50 * ONE);
+        set_context(&staker, 0, 2 * year);
+        assert_eq!(contract.get_claimable_rewards(staker.clone()).0, 150 * ONE);
+        contract.unstake(U128(1_000 * ONE));
+        set_context(&staker, 0, 3 * year);
+        assert_eq!(contract.get_claimable_rewards(staker.clone()).0, 200 * ONE);
+        assert_eq!(claim_at(&mut contract, &staker, 3 * year), 200 * ONE);
+    }
+
+    #[test]
+    fn transfer_events_carry_post_balances() {
+        let mut contract = setup();
+        contract.set_burn_bps(100);
+        let (sender, receiver) = (account("sender"), account("receiver"));
+        mint(&mut contract, &sender, 100 * ONE);
+        mint(&mut contract, &receiver, 5 * ONE);
+        set_context(&sender, 0, 0);
+        contract.tip(receiver.clone(), U128(10 * ONE));
+        let transfer = event_data("ft_transfer");
+        assert_eq!(transfer["old_owner_balance"], (90 * ONE).to_string());
+        assert_eq!(
+            transfer["new_owner_balance"],
+            (5 * ONE + 99 * ONE / 10).to_string()
+        );
+        let burn = event_data("ft_burn");
+        assert_eq!(burn["owner_id"], sender.as_str());
+        assert_eq!(burn["balance"], (90 * ONE).to_string());
+
+        let checkpoint = contract.get_state_checkpoint();
+        assert_eq!(checkpoint.total_supply.0, 105 * ONE - ONE / 10);
+        assert_eq!(checkpoint.total_burned.0, ONE / 10);
+        assert_eq!(checkpoint.holder_count, 2);
+        let last_seq = events().last().unwrap()["data"][0]["event_seq"].clone();
+        assert_eq!(last_seq, checkpoint.event_seq);
+    }
+
+    #[test]
+    fn slashed_unstake_events_carry_the_treasury_balance() {
+        let mut contract = setup();
+        contract.set_slashing(1_000, 60);
+        let staker = account("staker");
+        mint_and_stake(&mut contract, &staker, 100 * ONE);
+        set_context(&staker, 0, SECOND);
+        contract.unstake(U128(100 * ONE));
+        let unstake = event_data("unstake");
+        assert_eq!(unstake["balance"], (90 * ONE).to_string());
+        assert_eq!(unstake["treasury_id"], accounts(0).as_str());
+        assert_eq!(unstake["treasury_balance"], (10 * ONE).to_string());
+    }
+
+    #[test]
+    fn non_holder_referrers_are_accepted_while_the_requirement_is_off() {
+        let mut contract = setup();
+        assert!(!contract.is_referrer_holder_required());
+        set_context(&account("minter"), 0, 0);
+        contract.register_referral(account("stranger"));
+        assert_eq!(
+            contract.get_referrer(account("minter")),
+            Some(account("stranger"))
+        );
+    }
+
+    #[test]
+    fn holder_referrers_are_accepted_while_the_requirement_is_on() {
+        let mut contract = setup();
+        contract.set_require_referrer_holder(true);
+        assert!(contract.is_referrer_holder_required());
+        mint_and_stake(&mut contract, &account("staker"), ONE);
+        set_context(&account("minter"), 0, 0);
+        contract.register_referral(account("staker"));
+        assert_eq!(contract.get_referral_count(account("staker")), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "E63")]
+    fn non_holder_referrers_are_rejected_while_the_requirement_is_on() {
+        let mut contract = setup();
+        contract.set_require_referrer_holder(true);
+        set_context(&account("minter"), 0, 0);
+        contract.register_referral(account("stranger"));
+    }
+
+    /// Moves the treasury to `treasury.near` and seeds it with 50 tokens
+    /// bought back from `holder.near`.
+    fn setup_treasury_balance() -> Contract {
+        let mut contract = setup();
+        let id = contract.set_treasury(account("treasury"));
+        execute_after_timelock(&mut contract, id);
+        mint(&mut contract, &account("holder"), 100 * ONE);
+        contract.tip(account("treasury"), U128(50 * ONE));
+        set_context(&accounts(0), 0, 0);
+        contract
+    }
+
+    #[test]
+    fn treasury_burns_reduce_the_treasury_balance_and_supply() {
+        let mut contract = setup_treasury_balance();
+        contract.burn_treasury(U128(30 * ONE));
+        assert_eq!(contract.get_balance(account("treasury")).0, 20 * ONE);
+        assert_eq!(contract.get_total_supply().0, 70 * ONE);
+        assert_eq!(contract.get_total_burned().0, 30 * ONE);
+        assert_eq!(contract.get_burned_by(account("treasury")).0, 30 * ONE);
+        let burn = event_data("ft_burn");
+        assert_eq!(burn["owner_id"], "treasury.near");
+        assert_eq!(burn["amount"], (30 * ONE).to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "E4: Insufficient balance to burn")]
+    fn treasury_burns_above_the_treasury_balance_are_rejected() {
+        let mut contract = setup_treasury_balance();
+        contract.burn_treasury(U128(50 * ONE + 1));
+    }
+
+    #[test]
+    fn endorsements_count_each_account_once() {
+        let mut contract = setup();
+        let id = propose(&mut contract, false);
+        for endorser in ["ann", "ben"] {
+            set_context(&account(endorser), 0, 0);
+            contract.endorse(id);
+        }
+        let message = panic_message(|| contract.endorse(id));
+        assert_eq!(message, errors::ALREADY_ENDORSED);
+        let view = contract.get_proposal(id).unwrap();
+        assert_eq!(view.endorsement_count, 2);
+        assert_eq!(view.votes_for.0 + view.votes_against.0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "E36")]
+    fn cancelled_proposals_cannot_be_endorsed() {
+        let mut contract = setup();
+        let id = propose(&mut contract, false);
+        contract.cancel_proposal(id);
+        set_context(&account("ann"), 0, 0);
+        contract.endorse(id);
+    }
+
+    #[test]
+    #[should_panic(expected = "E37")]
+    fn finalized_proposals_cannot_be_endorsed() {
+        let mut contract = setup();
+        let id = propose(&mut contract, false);
+        finalize(&mut contract, id);
+        contract.endorse(id);
+    }
+
+    #[test]
+    fn apply_bps_rounds_in_the_requested_direction() {
+        assert_eq!(apply_bps(1, 333, false), 0);
+        assert_eq!(apply_bps(1, 333, true), 1);
+        assert_eq!(apply_bps(30_000, 333, false), 999);
+        assert_eq!(apply_bps(30_000, 333, true), 999);
+        assert_eq!(apply_bps(30_001, 333, true), 1_000);
+        assert_eq!(apply_bps(u128::MAX, 10_000, true), u128::MAX);
+    }
+
+    #[test]
+    fn tips_leak_no_dust_between_receiver_burn_and_pool() {
+        let mut contract = setup();
+        contract.set_burn_bps(333);
+        contract.set_fee_to_pool_bps(5_000);
+        let (sender, receiver) = (account("sender"), account("receiver"));
+        mint(&mut contract, &sender, ONE);
+        for amount in 1..=50 {
+            let sender_before = contract.get_balance(sender.clone()).0;
+            let receiver_before = contract.get_balance(receiver.clone()).0;
+            let pool_before = contract.get_reward_pool().0;
+            let supply_before = contract.get_total_supply().0;
+            set_context(&sender, 0, 0);
+            contract.tip(receiver.clone(), U128(amount));
+            let debited = sender_before - contract.get_balance(sender.clone()).0;
+            let credited = contract.get_balance(receiver.clone()).0 - receiver_before;
+            let pooled = contract.get_reward_pool().0 - pool_before;
+            let burned = supply_before - contract.get_total_supply().0;
+            assert_eq!(debited, amount);
+            assert_eq!(debited, credited + pooled + burned);
+            assert_eq!(pooled + burned, apply_bps(amount, 333, true));
+        }
+    }
+
+    #[test]
+    fn withdrawals_leak_no_dust_between_payout_fee_and_pool() {
+        let mut contract = setup();
+        contract.set_withdraw_config(333, U128(0));
+        contract.set_fee_to_pool_bps(5_000);
+        let holder = account("holder");
+        mint(&mut contract, &holder, ONE);
+        for amount in 1..=50 {
+            let treasury_before = contract.get_balance(accounts(0)).0;
+            let pool_before = contract.get_reward_pool().0;
+            set_context(&holder, 0, 0);
+            contract.withdraw(U128(amount));
+            let (_, paid) = transfers()[0].clone();
+            let fee = apply_bps(amount, 333, true);
+            set_context(&account("memecoin"), 0, 0);
+            contract.on_withdraw_resolved(holder.clone(), U128(amount), U128(fee), 0, Ok(()));
+            let to_treasury = contract.get_balance(accounts(0)).0 - treasury_before;
+            let pooled = contract.get_reward_pool().0 - pool_before;
+            assert_eq!(amount, paid + to_treasury + pooled);
+            assert_eq!(to_treasury + pooled, fee);
+        }
+    }
+
+    #[test]
+    fn tips_to_unregistered_receivers_register_them() {
+        let mut contract = setup();
+        let (sender, receiver) = (account("sender"), account("receiver"));
+        mint(&mut contract, &sender, 10 * ONE);
+        assert!(contract.is_registered(sender.clone()));
+        assert!(!contract.is_registered(receiver.clone()));
+        contract.tip(receiver.clone(), U128(4 * ONE));
+        assert!(contract.is_registered(receiver.clone()));
+        set_context(&sender, 0, 0);
+        contract.tip(receiver.clone(), U128(4 * ONE));
+        assert_eq!(contract.get_balance(receiver).0, 8 * ONE);
+    }
+
+    #[test]
+    #[should_panic(expected = "E64")]
+    fn tip_from_an_unregistered_sender_fails() {
+        let mut contract = setup();
+        set_context(&account("stranger"), 0, 0);
+        contract.tip(account("receiver"), U128(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "E3: Insufficient balance")]
+    fn tip_beyond_the_balance_fails_distinctly() {
+        let mut contract = setup();
+        let sender = account("sender");
+        mint(&mut contract, &sender, 10 * ONE);
+        contract.tip(account("receiver"), U128(11 * ONE));
+    }
+
+    #[test]
+    fn claims_draw_transfer_funded_pool_before_minting() {
+        let mut contract = setup();
+        contract.set_burn_bps(1_000);
+        contract.set_fee_to_pool_bps(5_000);
+        let (staker, trader) = (account("staker"), account("trader"));
+        mint_and_stake(&mut contract, &staker, 1_000 * ONE);
+        mint(&mut contract, &trader, 400 * ONE);
+        for _ in 0..4 {
+            set_context(&trader, 0, 0);
+            contract.tip(account("receiver"), U128(100 * ONE));
+        }
+        assert_eq!(contract.get_reward_pool().0, 20 * ONE);
+        let supply = contract.get_total_supply().0;
+        assert_eq!(claim_at(&mut contract, &staker, YEAR_NS as u64), 50 * ONE);
+        assert_eq!(contract.get_reward_pool().0, 0);
+        assert_eq!(contract.get_total_supply().0, supply + 30 * ONE);
+        let event = event_data("reward_pool_drawn");
+        assert_eq!(event["account_id"], staker.as_str());
+        assert_eq!(event["amount"], (20 * ONE).to_string());
+        assert_eq!(event["pool"], "0");
+    }
+
+    #[test]
+    fn claims_covered_by_the_pool_mint_nothing() {
+        let mut contract = setup();
+        contract.set_burn_bps(1_000);
+        contract.set_fee_to_pool_bps(10_000);
+        let (staker, trader) = (account("staker"), account("trader"));
+        mint_and_stake(&mut contract, &staker, 1_000 * ONE);
+        mint(&mut contract, &trader, 1_000 * ONE);
+        contract.tip(account("receiver"), U128(1_000 * ONE));
+        assert_eq!(contract.get_reward_pool().0, 100 * ONE);
+        let supply = contract.get_total_supply().0;
+        assert_eq!(claim_at(&mut contract, &staker, YEAR_NS as u64), 50 * ONE);
+        assert_eq!(contract.get_reward_pool().0, 50 * ONE);
+        assert_eq!(contract.get_total_supply().0, supply);
+    }
+
+    #[test]
+    fn nft_mint_can_gift_to_a_third_party() {
+        let mut contract = setup();
+        let (payer, friend) = (account("payer"), account("friend"));
+        set_context(&payer, ONE, 0);
+        contract.nft_mint("{}".to_string(), None, Some(friend.clone()));
+        assert_eq!(contract.nft_supply_for_owner(friend.clone()).0, 1);
+        assert_eq!(contract.nft_supply_for_owner(payer.clone()).0, 0);
+        let event = event_data("nft_mint");
+        assert_eq!(event["owner_id"], friend.as_str());
+        assert_eq!(event["creator_id"], payer.as_str());
+        let token = contract
+            .nft_token(event["token_id"].as_u64().unwrap())
+            .unwrap();
+        assert_eq!(token.owner_id, friend);
+        assert_eq!(token.creator_id, payer);
+    }
+
+    #[test]
+    fn nft_mint_defaults_to_the_caller() {
+        let mut contract = setup();
+        let payer = account("payer");
+        mint_nft(&mut contract, &payer);
+        assert_eq!(contract.nft_supply_for_owner(payer.clone()).0, 1);
+        assert_eq!(event_data("nft_mint")["owner_id"], payer.as_str());
+    }
+
+    #[test]
+    fn early_votes_record_bonused_weight() {
+        let mut contract = setup();
+        contract.set_early_vote_bonus(2_000, 1_000);
+        let (early, late) = (account("early"), account("late"));
+        mint(&mut contract, &early, 100 * ONE);
+        mint(&mut contract, &late, 100 * ONE);
+        let id = propose(&mut contract, false);
+        // The policy is fixed at creation, so later changes do not apply.
+        set_context(&accounts(0), 0, 0);
+        contract.set_early_vote_bonus(0, 0);
+        vote(&mut contract, &early, id, true);
+        set_context(&late, 0, DEFAULT_VOTING_PERIOD_SECONDS * SECOND / 2);
+        contract.vote(id, false);
+        assert_eq!(contract.get_vote(id, early).unwrap().weight.0, 120 * ONE);
+        assert_eq!(contract.get_vote(id, late).unwrap().weight.0, 100 * ONE);
+        let proposal = contract.get_proposal(id).unwrap();
+        assert_eq!(proposal.votes_for.0, 120 * ONE);
+        assert_eq!(proposal.votes_against.0, 100 * ONE);
+        assert_eq!(proposal.early_vote_bonus_bps, 2_000);
+        assert_eq!(
+            proposal.early_vote_ends,
+            DEFAULT_VOTING_PERIOD_SECONDS * SECOND / 10
+        );
+    }
+
+    #[test]
+    fn unregistered_accounts_read_as_empty() {
+        let mut contract = setup();
+        let stranger = account("stranger");
+        assert!(!contract.is_registered(stranger.clone()));
+        assert_eq!(contract.get_balance(stranger.clone()).0, 0);
+        assert_eq!(contract.get_balances(vec![stranger.clone()]), vec![U128(0)]);
+        assert_eq!(contract.get_claimable_rewards(stranger.clone()).0, 0);
+        // Staking everything keeps the account registered.
+        let staker = account("staker");
+        mint_and_stake(&mut contract, &staker, 10 * ONE);
+        assert!(contract.is_registered(staker.clone()));
+        assert_eq!(contract.get_balance(staker).0, 0);
+    }
+
+    #[test]
+    fn unregistered_accounts_cannot_spend() {
+        type Call = fn(&mut Contract);
+        let cases: [Call; 5] = [
+            |contract| contract.tip(account("receiver"), U128(ONE)),
+            |contract| contract.stake(U128(ONE)),
+            |contract| contract.burn(U128(ONE)),
+            |contract| {
+                contract.withdraw(U128(ONE));
+            },
+            |contract| {
+                contract.withdraw_all();
+            },
+        ];
+        for call in cases {
+            let mut contract = setup();
+            set_context(&account("stranger"), 0, 0);
+            assert_eq!(
+                panic_message(|| call(&mut contract)),
+                errors::ACCOUNT_NOT_REGISTERED
+            );
+        }
+    }
+
+    /// Accepts `usdc.near` at 5 units per NFT and prepays `buyer`'s NFT storage.
+    fn setup_accepted_token(buyer: &AccountId) -> Contract {
+        let mut contract = setup();
+        contract.set_accepted_token(account("usdc"), Some(U128(5)));
+        set_context(buyer, ONE, 0);
+        contract.deposit_nft_storage(None);
+        contract
+    }
+
+    fn buy_nft_msg() -> String {
+        json!({ "action": "buy_nft", "metadata": "{}" }).to_string()
+    }
+
+    #[test]
+    fn ft_on_transfer_buys_an_nft_and_refunds_the_excess() {
+        let buyer = account("buyer");
+        let mut contract = setup_accepted_token(&buyer);
+        set_context(&account("usdc"), 0, 0);
+        let unused = contract.ft_on_transfer(buyer.clone(), U128(8), buy_nft_msg());
+        assert_eq!(unused.0, 3);
+        assert_eq!(contract.nft_supply_for_owner(buyer.clone()).0, 1);
+        assert_eq!(contract.get_ft_proceeds(account("usdc")).0, 5);
+        assert!(contract.get_nft_storage_credit(buyer.clone()).0 < ONE);
+        let event = event_data("nft_bought_with_ft");
+        assert_eq!(event["owner_id"], buyer.as_str());
+        assert_eq!(event["ft_contract_id"], "usdc.near");
+    }
+
+    #[test]
+    fn ft_on_transfer_rejects_bad_transfers() {
+        let buyer = account("buyer");
+        let cases = [
+            ("dai", 8, buy_nft_msg(), errors::FT_NOT_ACCEPTED),
+            ("usdc", 4, buy_nft_msg(), errors::FT_PAYMENT_TOO_LOW),
+            ("usdc", 8, "{}".to_string(), errors::INVALID_TRANSFER_MSG),
+        ];
+        for (token, amount, msg, code) in cases {
+            let mut contract = setup_accepted_token(&buyer);
+            set_context(&account(token), 0, 0);
+            let message =
+                panic_message(|| contract.ft_on_transfer(buyer.clone(), U128(amount), msg));
+            assert!(message.starts_with(code), "{}", message);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "E76")]
+    fn ft_on_transfer_requires_prepaid_storage() {
+        let mut contract = setup();
+        contract.set_accepted_token(account("usdc"), Some(U128(5)));
+        set_context(&account("usdc"), 0, 0);
+        contract.ft_on_transfer(account("buyer"), U128(5), buy_nft_msg());
+    }
+
+    #[test]
+    fn effective_balance_nets_staked_and_vote_locked_tokens() {
+        let mut contract = setup();
+        contract.set_vote_lock(true);
+        let holder = account("holder");
+        mint_and_stake(&mut contract, &holder, 30 * ONE);
+        mint(&mut contract, &holder, 70 * ONE);
+        let id = propose(&mut contract, false);
+        vote(&mut contract, &holder, id, true);
+        mint(&mut contract, &holder, 20 * ONE);
+        let breakdown = contract.get_effective_balance(holder.clone());
+        assert_eq!(breakdown.liquid.0, 90 * ONE);
+        assert_eq!(breakdown.staked.0, 30 * ONE);
+        assert_eq!(breakdown.vote_locked.0, 70 * ONE);
+        assert_eq!(breakdown.spendable.0, 20 * ONE);
+        contract.tip(account("receiver"), breakdown.spendable);
+        let breakdown = contract.get_effective_balance(holder);
+        assert_eq!(breakdown.liquid.0, 70 * ONE);
+        assert_eq!(breakdown.spendable.0, 0);
+    }
+
+    #[test]
+    fn effective_balance_of_an_unknown_account_is_empty() {
+        let contract = setup();
+        let breakdown = contract.get_effective_balance(account("stranger"));
+        assert_eq!(breakdown.liquid.0, 0);
+        assert_eq!(breakdown.staked.0, 0);
+        assert_eq!(breakdown.vote_locked.0, 0);
+        assert_eq!(breakdown.spendable.0, 0);
+    }
+
+    #[test]
+    fn sweep_releases_expired_vote_locks_and_pays_the_caller() {
+        let mut contract = setup();
+        contract.set_vote_lock(true);
+        let (voter, idle, sweeper) = (account("voter"), account("idle"), account("sweeper"));
+        mint(&mut contract, &accounts(0), 10 * ONE);
+        mint(&mut contract, &voter, 50 * ONE);
+        mint(&mut contract, &idle, 50 * ONE);
+        let id = propose(&mut contract, false);
+        vote(&mut contract, &voter, id, true);
+        assert_eq!(contract.get_locked_balance(voter.clone()).0, 50 * ONE);
+        // Nothing is swept while voting is still open.
+        set_context(&sweeper, 0, 2 * SECOND);
+        assert_eq!(
+            contract.sweep_expired_locks(vec![voter.clone()], None, None),
+            0
+        );
+        set_context(&sweeper, 0, DEFAULT_VOTING_PERIOD_SECONDS * SECOND);
+        let swept = contract.sweep_expired_locks(vec![voter.clone(), idle], None, None);
+        assert_eq!(swept, 1);
+        assert_eq!(contract.get_locked_balance(voter.clone()).0, 0);
+        assert!(contract.vote_locks.get(&id).unwrap().is_empty());
+        assert_eq!(contract.get_balance(sweeper).0, SWEEP_REWARD);
+        assert_eq!(contract.get_balance(accounts(0)).0, 10 * ONE - SWEEP_REWARD);
+        assert_eq!(event_data("locks_swept")["accounts"], 1);
+    }
+
+    #[test]
+    fn sweep_clears_lapsed_cooldowns_without_a_reward() {
+        let sender = account("sender");
+        let mut contract = setup_cooldown(&sender, 0);
+        set_context(&account("sweeper"), 0, 60 * SECOND);
+        assert_eq!(
+            contract.sweep_expired_locks(vec![sender.clone()], None, None),
+            1
+        );
+        assert!(contract.last_transfer.get(&sender).is_none());
+        assert_eq!(contract.get_balance(account("sweeper")).0, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "E6: Too many accounts")]
+    fn sweep_rejects_oversized_batches() {
+        let mut contract = setup();
+        let accounts = (0..=MAX_BATCH_QUERY)
+            .map(|i| account(&format!("holder{}", i)))
+            .collect();
+        contract.sweep_expired_locks(accounts, None, None);
+    }
+
+    #[test]
+    fn short_voting_periods_are_clamped_to_the_minimum() {
+        const HOUR: u64 = 60 * 60;
+        let mut contract = setup();
+        contract.set_voting_period(HOUR);
+        assert_eq!(contract.get_min_vote_duration(), 24 * HOUR);
+        let id = propose(&mut contract, false);
+        assert_eq!(
+            contract.get_proposal(id).unwrap().deadline,
+            24 * HOUR * SECOND
+        );
+        set_context(&accounts(0), 0, HOUR * SECOND);
+        assert_eq!(
+            panic_message(|| contract.finalize_proposal(id)),
+            errors::VOTING_NOT_ENDED
+        );
+    }
+
+    #[test]
+    fn lowering_the_minimum_applies_to_new_proposals() {
+        const HOUR: u64 = 60 * 60;
+        let mut contract = setup();
+        contract.set_voting_period(HOUR);
+        let clamped = propose(&mut contract, false);
+        contract.set_min_vote_duration(0);
+        assert_eq!(event_data("min_vote_duration_updated")["seconds"], 0);
+        let unclamped = propose(&mut contract, false);
+        assert_eq!(
+            contract.get_proposal(clamped).unwrap().deadline,
+            24 * HOUR * SECOND
+        );
+        assert_eq!(
+            contract.get_proposal(unclamped).unwrap().deadline,
+            HOUR * SECOND
+        );
+    }
+
+    #[test]
+    fn withdraw_all_leaves_staked_tokens_alone() {
+        let holder = account("holder");
+        let mut contract = setup_holder(&holder);
+        set_context(&accounts(0), 0, 0);
+        contract.set_withdraw_config(100, U128(0));
+        set_context(&holder, 0, 0);
+        contract.withdraw_all();
+        assert_eq!(contract.get_balance(holder.clone()).0, 0);
+        assert_eq!(
+            contract.get_staked_batch(vec![holder.clone()]),
+            vec![U128(10 * ONE)]
+        );
+        assert_eq!(transfers(), vec![(holder.clone(), 99 * ONE)]);
+        assert_eq!(
+            function_calls(),
+            vec![(account("memecoin"), "on_withdraw_resolved".to_string())]
+        );
+        let event = event_data("withdraw_all");
+        assert_eq!(event["amount"], (100 * ONE).to_string());
+        assert_eq!(event["fee"], ONE.to_string());
+        assert_eq!(event["net"], (99 * ONE).to_string());
+    }
+
+    #[test]
+    fn failed_withdraw_all_credits_the_full_amount_back() {
+        let holder = account("holder");
+        let mut contract = setup_holder(&holder);
+        set_context(&accounts(0), 0, 0);
+        contract.set_withdraw_config(100, U128(0));
+        set_context(&holder, 0, 0);
+        contract.withdraw_all();
+        set_context(&account("memecoin"), 0, 0);
+        contract.on_withdraw_resolved(
+            holder.clone(),
+            U128(100 * ONE),
+            U128(ONE),
+            0,
+            Err(PromiseError::Failed),
+        );
+        assert_eq!(contract.get_balance(holder).0, 100 * ONE);
+        assert_eq!(contract.get_balance(accounts(0)).0, 0);
+    }
+
+    #[test]
+    fn proposal_view_reports_voter_count_and_turnout() {
+        let mut contract = setup();
+        let voters = [
+            (account("ann"), 20),
+            (account("ben"), 30),
+            (account("cat"), 50),
+        ];
+        for (voter, amount) in &voters {
+            mint(&mut contract, voter, amount * ONE);
+        }
+        mint(&mut contract, &account("abstainer"), 100 * ONE);
+        let id = propose(&mut contract, false);
+        // Tokens minted after creation do not dilute the snapshot.
+        mint(&mut contract, &account("late"), 1_000 * ONE);
+        for (i, (voter, _)) in voters.iter().enumerate() {
+            vote(&mut contract, voter, id, i != 1);
+        }
+        let view = contract.get_proposal(id).unwrap();
+        assert_eq!(view.voter_count, 3);
+        assert_eq!(view.votes_for.0, 70 * ONE);
+        assert_eq!(view.votes_against.0, 30 * ONE);
+        assert_eq!(view.total_votes.0, 100 * ONE);
+        assert_eq!(view.supply_snapshot.0, 200 * ONE);
+        assert_eq!(view.turnout_bps, Some(5_000));
+        let listed = contract.get_proposals(None, None);
+        assert_eq!(listed[0].voter_count, 3);
+        assert_eq!(listed[0].turnout_bps, Some(5_000));
+    }
+
+    #[test]
+    fn set_metadata_icon_updates_and_clears_the_icon() {
+        let mut contract = setup();
+        let icon = "data:image/svg+xml,<svg/>".to_string();
+        contract.set_metadata_icon(Some(icon.clone()));
+        assert_eq!(contract.get_metadata_icon(), Some(icon.clone()));
+        assert_eq!(event_data("metadata_icon_updated")["icon"], icon);
+        set_context(&accounts(0), 0, 0);
+        contract.set_metadata_icon(None);
+        assert_eq!(contract.get_metadata_icon(), None);
+    }
+
+    #[test]
+    fn set_metadata_icon_rejects_malformed_or_oversized_icons() {
+        let oversized = format!("data:image/png;base64,{}", "A".repeat(MAX_ICON_LEN));
+        let cases = [
+            (
+                "https://example.com/icon.png".to_string(),
+                "Icon must be a data:image/ URI",
+            ),
+            (
+                "data:text/html,<script/>".to_string(),
+                "Icon must be a data:image/ URI",
+            ),
+            (oversized.clone(), "Icon too large"),
+        ];
+        for (icon, reason) in cases {
+            let mut contract = setup();
+            assert_eq!(
+                panic_message(|| contract.set_metadata_icon(Some(icon.clone()))),
+                reason
+            );
+            assert!(!contract.dry_run_action(ProposalAction::SetMetadataIcon { icon: Some(icon) }));
+            assert_eq!(contract.get_metadata_icon(), None);
+        }
+    }
+
+    #[test]
+    fn passed_proposals_can_set_the_icon() {
+        let mut contract = setup();
+        let icon = "data:image/png;base64,AAAA".to_string();
+        mint(&mut contract, &account("voter"), 50 * ONE);
+        set_context(&accounts(0), 0, 0);
+        contract.propose(
+            "Rebrand with a new icon".to_string(),
+            None,
+            None,
+            None,
+            Some(ProposalAction::SetMetadataIcon {
+                icon: Some(icon.clone()),
+            }),
+        );
+        vote(&mut contract, &account("voter"), 0, true);
+        end_veto_window(&contract, 0);
+        contract.finalize_proposal(0);
+        assert_eq!(contract.get_metadata_icon(), Some(icon));
+    }
+
+    #[test]
+    #[should_panic(expected = "Reentrant call")]
+    fn nested_guarded_calls_panic() {
+        let holder = account("holder");
+        let mut contract = setup_holder(&holder);
+        contract.with_lock(|this| {
+            this.withdraw(U128(ONE));
+        });
+    }
+
+    #[test]
+    fn guarded_calls_release_the_lock() {
+        let holder = account("holder");
+        let mut contract = setup_holder(&holder);
+        contract.withdraw(U128(ONE));
+        assert!(!contract.locked);
+        set_context(&account("memecoin"), 0, 0);
+        contract.on_withdraw_resolved(holder.clone(), U128(ONE), U128(0), 0, Ok(()));
+        assert!(!contract.locked);
+        set_context(&holder, 0, 0);
+        contract.withdraw(U128(ONE));
+        assert_eq!(contract.get_balance(holder).0, 98 * ONE);
+    }
+
+    #[test]
+    fn inflation_counters_track_each_supply_source() {
+        let (mut contract, referrer) = mint_referred(false);
+        let minter = account("minter");
+        let stats = contract.get_inflation_rate();
+        assert_eq!(stats.total_deposit_minted.0, 100 * ONE);
+        assert_eq!(stats.total_referral_minted.0, ONE);
+        assert_eq!(stats.total_reward_minted.0, 0);
+
+        contract.stake(U128(100 * ONE));
+        let stats = contract.get_inflation_rate();
+        assert_eq!(stats.annual_emission.0, 5 * ONE);
+        assert_eq!(stats.annual_inflation_bps, mul_div(5, 10_000, 101) as u64);
+
+        claim_at(&mut contract, &minter, YEAR_NS as u64);
+        assert_eq!(contract.get_inflation_rate().total_reward_minted.0, 5 * ONE);
+
+        set_context(&accounts(0), 0, 0);
+        contract.grant_role(account("bot"), Role::Minter);
+        set_context(&account("bot"), 0, 0);
+        contract.mint_to(referrer.clone(), U128(7 * ONE));
+        assert_eq!(contract.get_inflation_rate().total_minter_minted.0, 7 * ONE);
+
+        set_context(&referrer, 0, 0);
+        contract.burn(U128(2 * ONE));
+        let stats = contract.get_inflation_rate();
+        assert_eq!(stats.total_burned.0, 2 * ONE);
+        assert_eq!(stats.total_deposit_minted.0, 100 * ONE);
+        assert_eq!(
+            contract.get_total_supply().0,
+            stats.total_deposit_minted.0
+                + stats.total_referral_minted.0
+                + stats.total_reward_minted.0
+                + stats.total_minter_minted.0
+                - stats.total_burned.0
+        );
+    }
+
+    #[test]
+    fn dry_run_checks_each_action_variant_at_its_bounds() {
+        let mut contract = setup();
+        mint(&mut contract, &accounts(0), 10 * ONE);
+        let schedule =
+            |initial_rate_bps, halving_interval_seconds| ProposalAction::SetEmissionSchedule {
+                schedule: Some(EmissionSchedule {
+                    start: 0,
+                    initial_rate_bps,
+                    halving_interval_seconds,
+                }),
+            };
+        let cases = [
+            (
+                ProposalAction::SetRewardRate {
+                    bps: MAX_REWARD_RATE_BPS,
+                },
+                true,
+            ),
+            (
+                ProposalAction::SetRewardRate {
+                    bps: MAX_REWARD_RATE_BPS + 1,
+                },
+                false,
+            ),
+            (ProposalAction::SetBurnBps { bps: MAX_BURN_BPS }, true),
+            (
+                ProposalAction::SetBurnBps {
+                    bps: MAX_BURN_BPS + 1,
+                },
+                false,
+            ),
+            (
+                ProposalAction::SetTreasury {
+                    treasury: account("vault"),
+                },
+                true,
+            ),
+            (ProposalAction::SetPaused { paused: true }, true),
+            (ProposalAction::SetEmissionSchedule { schedule: None }, true),
+            (schedule(MAX_REWARD_RATE_BPS, 1), true),
+            (schedule(MAX_REWARD_RATE_BPS + 1, 1), false),
+            (schedule(100, 0), false),
+            (
+                ProposalAction::BurnTreasury {
+                    amount: U128(10 * ONE),
+                },
+                true,
+            ),
+            (
+                ProposalAction::BurnTreasury {
+                    amount: U128(10 * ONE + 1),
+                },
+                false,
+            ),
+            (ProposalAction::BurnTreasury { amount: U128(0) }, false),
+            (
+                ProposalAction::SetMinVoteDuration {
+                    seconds: MAX_VOTING_PERIOD_SECONDS,
+                },
+                true,
+            ),
+            (
+                ProposalAction::SetMinVoteDuration {
+                    seconds: MAX_VOTING_PERIOD_SECONDS + 1,
+                },
+                false,
+            ),
+            (ProposalAction::SetMetadataIcon { icon: None }, true),
+            (
+                ProposalAction::SetMetadataIcon {
+                    icon: Some("icon.png".to_string()),
+                },
+                false,
+            ),
+            (
+                ProposalAction::SetFinalizationGrace {
+                    seconds: MAX_VOTING_PERIOD_SECONDS,
+                },
+                true,
+            ),
+            (
+                ProposalAction::SetFinalizationGrace {
+                    seconds: MAX_VOTING_PERIOD_SECONDS + 1,
+                },
+                false,
+            ),
+        ];
+        for (action, valid) in cases {
+            let description = format!("{:?}", action);
+            assert_eq!(contract.dry_run_action(action), valid, "{}", description);
+        }
+    }
+
+    #[test]
+    fn propose_rejects_invalid_actions_at_creation() {
+        let mut contract = setup();
+        let message = panic_message(|| {
+            contract.propose(
+                "Raise the transfer burn".to_string(),
+                None,
+                None,
+                None,
+                Some(ProposalAction::SetBurnBps {
+                    bps: MAX_BURN_BPS + 1,
+                }),
+            )
+        });
+        assert!(
+            message.contains("Invalid proposal action: Transfer burn too high"),
+            "{}",
+            message
+        );
+        assert_eq!(contract.next_proposal_id, 0);
+    }
+
+    #[test]
+    fn rewards_are_proportional_to_stake_duration() {
+        let mut contract = setup();
+        let (early, late) = (account("early"), account("late"));
+        let year = YEAR_NS as u64;
+        mint_and_stake(&mut contract, &early, 100 * ONE);
+        mint(&mut contract, &late, 100 * ONE);
+        set_context(&late, 0, year / 2);
+        contract.stake(U128(100 * ONE));
+        set_context(&early, 0, year);
+        let year_seconds = (year / SECOND) as u128;
+        assert_eq!(
+            contract.get_weighted_stake_seconds(early.clone()).0,
+            100 * year_seconds
+        );
+        assert_eq!(
+            contract.get_weighted_stake_seconds(late.clone()).0,
+            50 * year_seconds
+        );
+        let early_reward = claim_at(&mut contract, &early, year);
+        let late_reward = claim_at(&mut contract, &late, year);
+        assert_eq!(early_reward, 5 * ONE);
+        assert_eq!(late_reward, 5 * ONE / 2);
+    }
+
+    #[test]
+    fn stake_seconds_accumulate_across_stake_changes() {
+        let mut contract = setup();
+        let staker = account("staker");
+        mint_and_stake(&mut contract, &staker, 10 * ONE);
+        mint(&mut contract, &staker, 10 * ONE);
+        set_context(&staker, 0, 100 * SECOND);
+        contract.stake(U128(10 * ONE));
+        set_context(&staker, 0, 150 * SECOND);
+        contract.unstake(U128(15 * ONE));
+        set_context(&staker, 0, 250 * SECOND);
+        assert_eq!(
+            contract.get_weighted_stake_seconds(staker).0,
+            10 * 100 + 20 * 50 + 5 * 100
+        );
+    }
+
+    fn ids_with_status(contract: &Contract, status: ProposalStatus) -> Vec<u64> {
+        contract
+            .get_proposals_by_status(status, 0, MAX_PAGE_LIMIT)
+            .into_iter()
+            .map(|proposal| proposal.id)
+            .collect()
+    }
+
+    #[test]
+    fn proposals_move_between_status_buckets() {
+        let mut contract = setup();
+        let voter = account("voter");
+        let vetoed = setup_treasury_burn(&mut contract);
+        let passing = propose(&mut contract, false);
+        let failing = propose(&mut contract, false);
+        let cancelled = propose(&mut contract, false);
+        vote(&mut contract, &voter, passing, true);
+        vote(&mut contract, &voter, failing, false);
+        set_context(&accounts(0), 0, SECOND);
+        contract.cancel_proposal(cancelled);
+        assert_eq!(
+            ids_with_status(&contract, ProposalStatus::Active),
+            vec![vetoed, passing, failing]
+        );
+        assert_eq!(
+            ids_with_status(&contract, ProposalStatus::Cancelled),
+            vec![cancelled]
+        );
+
+        contract.veto_proposal(vetoed, "Treasury funds are committed".to_string());
+        assert_eq!(
+            ids_with_status(&contract, ProposalStatus::Vetoed),
+            vec![vetoed]
+        );
+
+        let deadline = contract.get_proposal(passing).unwrap().deadline;
+        set_context(&accounts(0), 0, deadline);
+        assert!(ids_with_status(&contract, ProposalStatus::Active).is_empty());
+        assert_eq!(
+            ids_with_status(&contract, ProposalStatus::Ended),
+            vec![passing, failing]
+        );
+
+        contract.finalize_proposal(passing);
+        contract.finalize_proposal(failing);
+        assert!(ids_with_status(&contract, ProposalStatus::Ended).is_empty());
+        assert_eq!(
+            ids_with_status(&contract, ProposalStatus::Passed),
+            vec![passing]
+        );
+        assert_eq!(
+            ids_with_status(&contract, ProposalStatus::Failed),
+            vec![failing]
+        );
+        assert_eq!(
+            contract.get_proposal(vetoed).unwrap().status,
+            ProposalStatus::Vetoed
+        );
+    }
+
+    #[test]
+    fn status_pages_are_bounded_by_index() {
+        let mut contract = setup();
+        for _ in 0..3 {
+            propose(&mut contract, false);
+        }
+        let page = contract.get_proposals_by_status(ProposalStatus::Active, 1, 1);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id, 1);
+        assert!(contract
+            .get_proposals_by_status(ProposalStatus::Active, 3, 10)
+            .is_empty());
+    }
+
+    #[test]
+    fn finalization_waits_out_the_grace_period() {
+        const GRACE: u64 = 60 * 60;
+        let mut contract = setup();
+        contract.set_finalization_grace(GRACE);
+        assert_eq!(
+            contract.get_contract_config().finalization_grace_seconds,
+            GRACE
+        );
+        let voter = account("voter");
+        mint(&mut contract, &voter, 50 * ONE);
+        let id = propose(&mut contract, false);
+        vote(&mut contract, &voter, id, true);
+        let deadline = contract.get_proposal(id).unwrap().deadline;
+        set_context(&accounts(0), 0, deadline);
+        assert_eq!(
+            panic_message(|| contract.finalize_proposal(id)),
+            errors::FINALIZATION_GRACE
+        );
+        set_context(&account("latecomer"), 0, deadline + SECOND);
+        assert_eq!(
+            panic_message(|| contract.vote(id, false)),
+            errors::VOTING_ENDED
+        );
+        set_context(&accounts(0), 0, deadline + GRACE * SECOND - 1);
+        assert_eq!(
+            panic_message(|| contract.finalize_proposal(id)),
+            errors::FINALIZATION_GRACE
+        );
+        set_context(&accounts(0), 0, deadline + GRACE * SECOND);
+        contract.finalize_proposal(id);
+        assert!(contract.get_proposal(id).unwrap().passed);
+    }
+
+    #[test]
+    fn passed_proposals_can_set_the_grace_period() {
+        let mut contract = setup();
+        mint(&mut contract, &account("voter"), 50 * ONE);
+        set_context(&accounts(0), 0, 0);
+        contract.propose(
+            "Add a finalization grace period".to_string(),
+            None,
+            None,
+            None,
+            Some(ProposalAction::SetFinalizationGrace { seconds: 600 }),
+        );
+        vote(&mut contract, &account("voter"), 0, true);
+        end_veto_window(&contract, 0);
+        contract.finalize_proposal(0);
+        assert_eq!(contract.get_finalization_grace(), 600);
+    }
+
+    #[test]
+    fn contract_stats_reflect_mint_stake_and_burn() {
+        let mut contract = setup();
+        let holder = account("holder");
+        mint(&mut contract, &accounts(0), 20 * ONE);
+        mint(&mut contract, &holder, 100 * ONE);
+        let stats = contract.get_contract_stats();
+        assert_eq!(stats.total_supply.0, 120 * ONE);
+        assert_eq!(stats.circulating_supply.0, 100 * ONE);
+        assert_eq!(stats.holder_count, 2);
+        assert_eq!(stats.treasury_balance.0, 20 * ONE);
+
+        set_context(&holder, 0, 0);
+        contract.stake(U128(30 * ONE));
+        let stats = contract.get_contract_stats();
+        assert_eq!(stats.total_staked.0, 30 * ONE);
+        assert_eq!(stats.staker_count, 1);
+
+        set_context(&holder, 0, 0);
+        contract.burn(U128(10 * ONE));
+        propose(&mut contract, false);
+        let stats = contract.get_contract_stats();
+        assert_eq!(stats.total_supply.0, 110 * ONE);
+        assert_eq!(stats.circulating_supply.0, 90 * ONE);
+        assert_eq!(stats.total_burned.0, 10 * ONE);
+        assert_eq!(stats.holder_count, 2);
+        assert_eq!(stats.reward_pool.0, 0);
+        assert_eq!(stats.next_proposal_id, 1);
+        assert_eq!(stats.active_proposal_count, 1);
+        assert!(!stats.paused);
+    }
+
+    #[test]
+    fn stakers_can_cancel_their_own_proposals() {
+        let mut contract = propose_as_staker(100 * ONE);
+        set_context(&account("proposer"), 0, SECOND);
+        contract.cancel_proposal(0);
+        let proposal = contract.get_proposal(0).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Cancelled);
+        assert_eq!(contract.get_active_proposal_count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "E77")]
+    fn third_parties_cannot_cancel_proposals() {
+        let mut contract = propose_as_staker(100 * ONE);
+        set_context(&account("stranger"), 0, SECOND);
+        contract.cancel_proposal(0);
+    }
+
+    #[test]
+    fn nft_events_carry_no_token_balances() {
+        let mut contract = setup();
+        let (ann, ben) = (account("ann"), account("ben"));
+        mint(&mut contract, &ann, 10 * ONE);
+        mint(&mut contract, &ben, 10 * ONE);
+        mint_nft(&mut contract, &ann);
+        let minted = event_data("nft_mint");
+        assert!(minted.get("balance").is_none());
+        let token_id = minted["token_id"].as_u64().unwrap();
+        set_context(&ann, 1, 0);
+        contract.nft_transfer(ben.clone(), token_id, None);
+        let transfer = event_data("nft_transfer");
+        assert_eq!(transfer["new_owner_id"], ben.as_str());
+        assert!(transfer.get("old_owner_balance").is_none());
+        assert!(transfer.get("new_owner_balance").is_none());
+        set_context(&ann, 0, 0);
+        contract.register_referral(ben);
+        assert!(event_data("referral_registered").get("balance").is_none());
+    }
+
+    #[test]
+    fn stake_batch_lists_post_balances_beside_accounts() {
+        let mut contract = setup();
+        let (ann, ben) = (account("ann"), account("ben"));
+        mint(&mut contract, &ann, 10 * ONE);
+        mint(&mut contract, &ben, 20 * ONE);
+        set_context(&accounts(0), 0, 0);
+        contract.admin_stake_for(
+            vec![ann.clone(), ben.clone()],
+            vec![U128(4 * ONE), U128(5 * ONE)],
+        );
+        let events = events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["data"].as_array().unwrap().len(), 1);
+        let batch = event_data("stake_batch");
+        assert_eq!(batch["account_ids"], json!([ann, ben]));
+        assert_eq!(batch["amounts"], json!([U128(4 * ONE), U128(5 * ONE)]));
+        assert_eq!(batch["balances"], json!([U128(6 * ONE), U128(15 * ONE)]));
+    }
+
+    #[test]
+    fn token_moving_events_carry_post_balances() {
+        let mut contract = setup();
+        let staker = account("staker");
+        mint(&mut contract, &staker, 10 * ONE);
+        contract.stake(U128(4 * ONE));
+        assert_eq!(event_data("stake")["balance"], (6 * ONE).to_string());
     }
 }